@@ -0,0 +1,31 @@
+// Feed arbitrary socket2 data through the event framer in arbitrarily sized
+// chunks. Run with `cargo +nightly fuzz run event_parser` from the repo root.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/event.rs"]
+#[allow(dead_code)]
+mod event;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte picks the read size, so truncated lines get split at
+    // every possible boundary
+    let Some((&chunk, stream)) = data.split_first() else {
+        return;
+    };
+    let chunk = usize::from(chunk).max(1);
+
+    let mut framer = event::Framer::new();
+    for bytes in stream.chunks(chunk) {
+        framer.push(bytes);
+        while let Some(event) = framer.next_event() {
+            assert!(!event.name.is_empty());
+            assert!(!event.name.contains('\n') && !event.data.contains('\n'));
+        }
+    }
+
+    for line in stream.split(|b| *b == b'\n') {
+        let _ = event::parse_line(line);
+    }
+});
@@ -0,0 +1,131 @@
+use std::fs;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::{audit, ipc, role};
+use crate::{dispatch_batch, get_clients, WorkspaceMonitorMap};
+
+// A layout.toml for `hyprws arrange`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Layout {
+    #[serde(default, rename = "window")]
+    windows: Vec<Placement>,
+}
+
+// [[window]] entry: where windows of a class belong
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Placement {
+    // Window class (regular expression)
+    class: String,
+    // Started when no window of the class is open
+    command: Option<String>,
+    // Workspace group (1-10)
+    workspace: i32,
+    // Monitor (or role) whose workspace of the group to use; the first one if unset
+    monitor: Option<String>,
+    floating: Option<bool>,
+    // Floating geometry in logical pixels
+    size: Option<[i32; 2]>,
+    position: Option<[i32; 2]>,
+}
+
+impl Placement {
+    // Rules for `exec [RULES] COMMAND` so a launched window starts in place
+    fn exec_rules(&self, workspace: i32) -> String {
+        let mut rules = vec![format!("workspace {} silent", workspace)];
+        match self.floating {
+            Some(true) => rules.push("float".to_string()),
+            Some(false) => rules.push("tile".to_string()),
+            None => {}
+        }
+        if let Some([width, height]) = self.size {
+            rules.push(format!("size {} {}", width, height));
+        }
+        if let Some([x, y]) = self.position {
+            rules.push(format!("move {} {}", x, y));
+        }
+        rules.join(";")
+    }
+}
+
+// Workspace number for a placement under the current mapping
+fn resolve(placement: &Placement, maps: &[WorkspaceMonitorMap], config: &Config) -> Option<i32> {
+    let mut candidates = maps.iter().filter(|m| m.workspace % 10 == placement.workspace % 10);
+    match &placement.monitor {
+        Some(monitor) => {
+            let monitor = role::resolve(monitor, config)?;
+            candidates.find(|m| m.monitor == monitor).map(|m| m.workspace)
+        }
+        None => candidates.next().map(|m| m.workspace),
+    }
+}
+
+// exec rules are separated by ';', which would split a batch, so launches
+// go through their own request
+fn launch(rules: &str, command: &str) {
+    let dispatch = format!("exec [{}] {}", rules, command);
+    audit::record(std::slice::from_ref(&dispatch));
+    if let Err(e) = ipc::client().and_then(|ipc| ipc.request(&format!("dispatch {}", dispatch))) {
+        eprintln!("Error launching '{}': {}", command, e);
+    }
+}
+
+// Converge the session to a layout file: move open windows into place and
+// launch the ones that are missing
+pub fn arrange(path: &str, maps: &[WorkspaceMonitorMap], config: &Config) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+    let layout: Layout = toml::from_str(&contents).map_err(|e| format!("{}: {}", path, e))?;
+
+    let clients = get_clients();
+    let mut claimed: Vec<&str> = Vec::new();
+    let mut dispatches = Vec::new();
+
+    for placement in &layout.windows {
+        let regex = Regex::new(&format!("^(?:{})$", placement.class))
+            .map_err(|e| format!("Invalid class '{}': {}", placement.class, e))?;
+        let Some(workspace) = resolve(placement, maps, config) else {
+            eprintln!(
+                "Warning: no workspace of group {} for '{}', skipping",
+                placement.workspace, placement.class
+            );
+            continue;
+        };
+
+        let window = clients
+            .iter()
+            .find(|c| regex.is_match(&c.class) && !claimed.contains(&c.address.as_str()));
+        let Some(window) = window else {
+            if let Some(command) = &placement.command {
+                println!("Launching {} on workspace {}", placement.class, workspace);
+                launch(&placement.exec_rules(workspace), command);
+            }
+            continue;
+        };
+        claimed.push(&window.address);
+
+        let address = &window.address;
+        if window.workspace.id != workspace {
+            println!("Moving {} to workspace {}", window.class, workspace);
+            dispatches.push(format!("movetoworkspacesilent {},address:{}", workspace, address));
+        }
+        match placement.floating {
+            Some(true) if !window.floating => dispatches.push(format!("setfloating address:{}", address)),
+            Some(false) if window.floating => dispatches.push(format!("settiled address:{}", address)),
+            _ => {}
+        }
+        if let Some([width, height]) = placement.size {
+            dispatches.push(format!("resizewindowpixel exact {} {},address:{}", width, height, address));
+        }
+        if let Some([x, y]) = placement.position {
+            dispatches.push(format!("movewindowpixel exact {} {},address:{}", x, y, address));
+        }
+    }
+
+    if !dispatches.is_empty() {
+        dispatch_batch(&dispatches);
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::usage::utc_offset;
+use crate::{create_runtime_dir, instance_suffix, lock, runtime_path, unix_time};
+
+/// Most dispatches kept in the log
+const CAPACITY: usize = 500;
+
+/// Size past which the log is cut back to CAPACITY; about twice CAPACITY
+/// entries of typical length
+const TRIM_SIZE: u64 = 2 * CAPACITY as u64 * 100;
+
+thread_local! {
+    // What the current thread is acting on, e.g. a CLI command or an event
+    static TRIGGER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+// One dispatch sent to Hyprland
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    time: u64,
+    trigger: String,
+    dispatch: String,
+}
+
+fn log_path() -> String {
+    runtime_path(&format!("dispatches{}.jsonl", instance_suffix()))
+}
+
+// Attribute dispatches sent from this thread from now on
+pub fn set_trigger(trigger: String) {
+    TRIGGER.with(|t| *t.borrow_mut() = trigger);
+}
+
+fn read_entries(path: &str) -> Vec<Entry> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+// Append dispatches to the log. It's only read back and cut down to the
+// newest CAPACITY entries once it grows past TRIM_SIZE, to keep switching
+// cheap.
+pub fn record(dispatches: &[String]) {
+    let path = log_path();
+    let trigger = TRIGGER.with(|t| t.borrow().clone());
+    let time = unix_time();
+    let lines: String = dispatches
+        .iter()
+        .map(|dispatch| Entry {
+            time,
+            trigger: trigger.clone(),
+            dispatch: dispatch.clone(),
+        })
+        .filter_map(|entry| serde_json::to_string(&entry).ok())
+        .map(|line| line + "\n")
+        .collect();
+
+    let result = (|| {
+        create_runtime_dir()?;
+        let _lock = lock::exclusive(&path)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        file.write_all(lines.as_bytes())?;
+        if file.metadata()?.len() <= TRIM_SIZE {
+            return Ok(());
+        }
+        let entries = read_entries(&path);
+        let excess = entries.len().saturating_sub(CAPACITY);
+        let contents: String = entries[excess..]
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect();
+        lock::write_locked(&path, contents.as_bytes())
+    })();
+    if let Err(e) = result {
+        eprintln!("Warning: couldn't log dispatches: {}", e);
+    }
+}
+
+// Local date and time for a Unix timestamp, as YYYY-MM-DD HH:MM:SS
+fn format_time(time: u64, offset: i64) -> String {
+    let local = time as i64 + offset;
+    let (days, seconds) = (local.div_euclid(86400), local.rem_euclid(86400));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60
+    )
+}
+
+// Recent dispatches, oldest first
+pub fn show(json: bool) {
+    let entries = read_entries(&log_path());
+    if json {
+        match serde_json::to_string(&entries) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error serializing dispatch log: {}", e),
+        }
+        return;
+    }
+    if entries.is_empty() {
+        println!("No dispatches recorded");
+        return;
+    }
+
+    let offset = utc_offset();
+    for entry in entries {
+        println!("{}  {:<40}  {}", format_time(entry.time, offset), entry.dispatch, entry.trigger);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fs::{self, create_dir_all, File};
+use std::io::{self, BufReader};
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Config};
+use crate::{get_monitors, lock, paths};
+
+// Describes what a bundle contains and where it came from
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Manifest {
+    // Descriptions of the monitors connected when the bundle was made
+    monitors: Vec<String>,
+    // Hook script file name in the bundle -> its path relative to $HOME
+    hooks: HashMap<String, String>,
+}
+
+fn staging_dir() -> PathBuf {
+    PathBuf::from(format!("{}/bundle-{}", paths::cache_dir(), std::process::id()))
+}
+
+fn get_monitor_descriptions() -> Vec<String> {
+    get_monitors().into_iter().map(|m| m.description).collect()
+}
+
+// Script file invoked by a hook command, if the command starts with one
+fn hook_script_path(hook: &str) -> Option<PathBuf> {
+    let program = hook.split_whitespace().next()?;
+    let path = PathBuf::from(paths::expand_home(program));
+    path.is_file().then_some(path)
+}
+
+fn run_tar(args: &[&str]) -> io::Result<()> {
+    let status = std::process::Command::new("tar").args(args).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("tar exited with {}", status)))
+    }
+}
+
+// Package config.toml, hook scripts and ws.conf into a tar archive
+pub fn export(bundle_path: &str, config: &Config, ws_path: &str) -> io::Result<()> {
+    let staging = staging_dir();
+    let _ = fs::remove_dir_all(&staging);
+    create_dir_all(staging.join("hooks"))?;
+
+    let mut manifest = Manifest {
+        monitors: get_monitor_descriptions(),
+        ..Default::default()
+    };
+
+    // Keep the config's file name so its format survives the round trip
+    if let Some(path) = config::config_file_path() {
+        let name = Path::new(&path).file_name().unwrap_or_default();
+        fs::copy(&path, staging.join(name))?;
+    }
+    if Path::new(ws_path).exists() {
+        fs::copy(ws_path, staging.join("ws.conf"))?;
+    }
+
+    let hooks = config
+        .profiles
+        .values()
+        .flat_map(|p| p.on_enter.iter().chain(p.on_exit.iter()));
+    let home = paths::home();
+    for hook in hooks {
+        let Some(script) = hook_script_path(hook) else {
+            continue;
+        };
+        // Stored relative to $HOME so they land in the importing user's home
+        let Ok(relative) = script.strip_prefix(&home) else {
+            eprintln!("Warning: not bundling hook '{}' outside {}", script.display(), home);
+            continue;
+        };
+        let name = format!("{}-{}", manifest.hooks.len(), script.file_name().unwrap_or_default().to_string_lossy());
+        fs::copy(&script, staging.join("hooks").join(&name))?;
+        manifest.hooks.insert(name, relative.to_string_lossy().to_string());
+    }
+
+    let file = File::create(staging.join("manifest.json"))?;
+    serde_json::to_writer_pretty(file, &manifest).map_err(io::Error::other)?;
+
+    let result = run_tar(&["-cf", bundle_path, "-C", &staging.to_string_lossy(), "."]);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+// Restore a bundle made by export, warning about monitors that aren't connected
+pub fn import<F>(bundle_path: &str, ws_path: &str, confirm: F) -> io::Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    lock::writable()?;
+    let staging = staging_dir();
+    let _ = fs::remove_dir_all(&staging);
+    create_dir_all(&staging)?;
+
+    let result = import_from(&staging, bundle_path, ws_path, confirm);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn import_from<F>(staging: &Path, bundle_path: &str, ws_path: &str, confirm: F) -> io::Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    run_tar(&["-xf", bundle_path, "-C", &staging.to_string_lossy()])?;
+
+    let file = File::open(staging.join("manifest.json"))?;
+    let manifest: Manifest = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let connected = get_monitor_descriptions();
+    let missing: Vec<_> = manifest.monitors.iter().filter(|m| !connected.contains(m)).collect();
+    for monitor in &missing {
+        eprintln!("Warning: bundle was made with monitor '{}' which is not connected", monitor);
+    }
+
+    let prompt = if missing.is_empty() {
+        "This will overwrite the hyprws config, ws.conf and hook scripts. Continue?".to_string()
+    } else {
+        format!("{} monitor(s) from the bundle are missing. Import anyway?", missing.len())
+    };
+    if !confirm(&prompt) {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "Import cancelled"));
+    }
+
+    if let Some(name) = config::CONFIG_FILES.iter().find(|name| staging.join(name).exists()) {
+        // Replace the file --config names, or put it in the config directory
+        let target = paths::config_override().unwrap_or_else(|| format!("{}/{}", paths::config_dir(), name));
+        if config::Format::from_path(&target) != config::Format::from_path(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The bundle holds {}, which can't replace {}", name, target),
+            ));
+        }
+        if let Some(parent) = Path::new(&target).parent() {
+            create_dir_all(parent)?;
+        }
+        // Written to a temporary file and renamed over the old config
+        lock::write(&target, &fs::read(staging.join(name))?)?;
+        // Configs in other formats would shadow the imported one
+        if paths::config_override().is_none() {
+            for other in config::CONFIG_FILES.iter().filter(|other| *other != name) {
+                let _ = fs::remove_file(format!("{}/{}", paths::config_dir(), other));
+            }
+        }
+    }
+    if staging.join("ws.conf").exists() {
+        lock::write(ws_path, &fs::read(staging.join("ws.conf"))?)?;
+    }
+
+    let home = paths::home();
+    for (name, relative) in &manifest.hooks {
+        // The staged script must be a file directly in hooks/
+        let mut components = Path::new(name).components();
+        let plain_name = matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none();
+        let source = staging.join("hooks").join(name);
+        if !plain_name || !fs::symlink_metadata(&source).is_ok_and(|m| m.file_type().is_file()) {
+            eprintln!("Warning: skipping hook '{}' that isn't a file in the bundle", name);
+            continue;
+        }
+        // Never write hook scripts outside the user's home
+        let inside_home = !relative.is_empty()
+            && Path::new(relative).components().all(|c| matches!(c, Component::Normal(_)));
+        if !inside_home {
+            eprintln!("Warning: skipping hook '{}' outside {}", relative, home);
+            continue;
+        }
+        let target = Path::new(&home).join(relative);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent)?;
+        }
+        fs::copy(source, target)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, NightLightBackend};
+use crate::{load_mapping, night_light, parse_workspace_rule, paths};
+
+// A `workspace = N, monitor:NAME` rule found in the user's Hyprland config
+struct NativeRule {
+    file: String,
+    line: usize,
+    workspace: i32,
+    monitor: String,
+}
+
+// A `source =` path, relative to the file that sources it
+pub fn expand(path: &str, relative_to: &Path) -> String {
+    let path = paths::expand_home(path);
+    match relative_to.parent() {
+        Some(dir) if !path.starts_with('/') => dir.join(path).to_string_lossy().into_owned(),
+        _ => path,
+    }
+}
+
+// A config line without its comment: `#` starts one, `##` stands for a
+// literal # (e.g. in a monitor description)
+pub fn strip_comment(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '#' {
+            stripped.push(c);
+        } else if chars.next_if_eq(&'#').is_some() {
+            stripped.push('#');
+        } else {
+            break;
+        }
+    }
+    stripped
+}
+
+// Collect native rules from a config file and the files it sources, except
+// the generated ones
+fn collect_rules(path: &str, generated: &[String], visited: &mut Vec<String>, rules: &mut Vec<NativeRule>) {
+    if visited.contains(&path.to_string()) || generated.contains(&path.to_string()) {
+        return;
+    }
+    visited.push(path.to_string());
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = strip_comment(line);
+        let line = line.trim();
+        if let Some(source) = line.strip_prefix("source").and_then(|rest| rest.trim_start().strip_prefix('=')) {
+            let source = expand(source.trim(), Path::new(path));
+            collect_rules(&source, generated, visited, rules);
+        } else if let Some(rule) = parse_workspace_rule(line).filter(|rule| rule.workspace > 0) {
+            rules.push(NativeRule {
+                file: path.to_string(),
+                line: index + 1,
+                workspace: rule.workspace,
+                monitor: rule.monitor,
+            });
+        }
+    }
+}
+
+// Validate config.toml and look for native workspace rules in hyprland.conf
+// that bind a generated workspace to another monitor. Returns the number of
+// problems found.
+pub fn check(ws_path: &str, config: &Config) -> usize {
+    let mut problems = 0;
+
+    match Config::load() {
+        Ok(_) => println!("config: ok"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => println!("config: none, using defaults"),
+        Err(e) => {
+            println!("config: {}", e);
+            problems += 1;
+        }
+    }
+
+    // Not an error: these profiles fall back to one temperature for all monitors
+    if let Some(backend) = config.night_light_backend.filter(|b| *b != NightLightBackend::Command) {
+        let mut names: Vec<_> = config
+            .profiles
+            .iter()
+            .filter(|(_, profile)| night_light::differs(&profile.night_light))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort();
+        for name in names {
+            println!(
+                "[profiles.{}] night_light: {:?} can't give monitors different temperatures; \
+                 all get the warmest one",
+                name, backend
+            );
+        }
+    }
+
+    let generated = load_mapping(ws_path, config);
+    // ws.conf and, with profile_outputs, the layout file it includes
+    let mut generated_files = vec![ws_path.to_string()];
+    if let Ok(contents) = fs::read_to_string(ws_path) {
+        generated_files.extend(contents.lines().filter_map(|line| {
+            let line = strip_comment(line);
+            line.trim().strip_prefix("source")?.trim_start().strip_prefix('=').map(|p| p.trim().to_string())
+        }));
+    }
+
+    let mut rules = Vec::new();
+    let hyprland_conf = paths::hyprland_conf();
+    collect_rules(&hyprland_conf, &generated_files, &mut Vec::new(), &mut rules);
+
+    for rule in &rules {
+        let Some(map) = generated.iter().find(|m| m.workspace == rule.workspace) else {
+            continue;
+        };
+        if map.monitor != rule.monitor {
+            println!(
+                "{}:{}: workspace {} is bound to {}, but {} puts it on {}",
+                rule.file, rule.line, rule.workspace, rule.monitor, ws_path, map.monitor
+            );
+            problems += 1;
+        }
+    }
+
+    if problems == 0 {
+        println!("No conflicting workspace rules");
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_only_unescaped_comments() {
+        assert_eq!(strip_comment("workspace = 1, monitor:DP-1 # main"), "workspace = 1, monitor:DP-1 ");
+        assert_eq!(strip_comment("# workspace = 1, monitor:DP-1"), "");
+        assert_eq!(strip_comment("monitor = desc:Dell U2720Q ##2,preferred"), "monitor = desc:Dell U2720Q #2,preferred");
+        assert_eq!(strip_comment("monitor = desc:A ##1 # B"), "monitor = desc:A #1 ");
+        assert_eq!(strip_comment("workspace = 1"), "workspace = 1");
+    }
+}
@@ -0,0 +1,476 @@
+use std::env;
+
+use crate::monitor::{self, Action, MonitorEvent};
+use crate::{
+    arrange, audit, bundle, check, config, control, daemon, debug, dropdown, groups, install, kanshi, migrate, nix,
+    peek, pin, profile, special, stash, state, target, usage,
+};
+use crate::{
+    assign_workspaces, confirm, focus_previous_window, get_connected_monitor_names, goto_window, instance_suffix,
+    load_mapping, offer_install, rotate_workspaces, send_workspace, show_overview, show_status,
+    switch_to_previous_workspace, workspace_command, Context, MAX_MONITORS, MAX_WORKSPACES,
+};
+
+fn display_help(program: &str) -> ! {
+    println!("Usage: {} [option] [workspace_number]", program);
+    println!("Options:");
+    println!("  -s | --workspace                           Switch workspace");
+    println!("  -s N --pull                                Bring group N's workspace from another monitor");
+    println!("  -m | --move                                Move workspace");
+    println!("  -m -s | --move --silent                    Move silently to workspace");
+    println!("  -m --class CLASS N | --all-floating N      Move matching windows on this workspace to group N");
+    println!("  -m -s N --to-monitor current|next|primary|ROLE|NAME");
+    println!("                                             Move silently to group N on a monitor");
+    println!("  assign                                     Rewrite ws.conf for the connected monitors");
+    println!("  profile NAME                               Apply a profile from config.toml");
+    println!("  config export|import BUNDLE.tar            Export or import config, hooks and ws.conf");
+    println!("  --generate-module                          Print the config as a home-manager module");
+    println!("  check | --check-config                     Validate the config and look for conflicting workspace rules");
+    println!("  import-kanshi KANSHI_CONFIG                Print kanshi profiles as hyprws [profiles] settings");
+    println!("  install                                    Source the generated ws.conf from hyprland.conf");
+    println!("  migrate                                    Create config.toml from an existing ws.conf");
+    println!("  --prev | prev                              Go back to the previously focused workspace on any monitor");
+    println!("  focus-prev                                 Focus the previously focused window on any workspace");
+    println!("  history --dispatches [--json]              Show recent dispatches and what triggered them");
+    println!("  usage [--heatmap] [--json]                 Show time spent per workspace (and hour of day)");
+    println!("  status [--json]                            Show the focused workspace, profile, submap and specials");
+    println!("  overview [--json]                          List workspaces with window counts and thumbnails");
+    println!("  list [--json]                              List groups with their descriptions");
+    println!("  pick                                       Choose a group with the picker and switch to it");
+    println!("  completions bash|zsh|fish                  Print a shell completion script");
+    println!("  dropdown [NAME]                            Toggle a dropdown terminal");
+    println!("  togglespecial | --toggle-special [NAME]    Toggle a special workspace and record what's visible");
+    println!("  stash                                      Hide the active window on a special workspace");
+    println!("  unstash [--pick]                           Restore the last (or a chosen) stashed window");
+    println!("  peek N [--duration 2s]                     Show group N on every monitor, then switch back");
+    println!("  rotate [--reverse]                         Move each monitor's workspace to the next monitor");
+    println!("  arrange LAYOUT.toml                        Move and launch windows to match a layout");
+    println!("  pin GROUP [--monitor NAME]                 Keep the active window in a group across monitor changes");
+    println!("  unpin                                      Unpin the active window");
+    println!("  set-primary NAME                           Monitor that receives orphaned workspaces");
+    println!("  send-workspace current|next|NAME [--swap]  Send the current workspace to a monitor,");
+    println!("                                             swapping with the same group's workspace there");
+    println!("  goto PATTERN                               Switch to the first window matching title/class");
+    println!("  daemon | --monitor                         Assign workspaces to monitors as they change, and");
+    println!("                                             answer -s/-m from memory over a control socket");
+    println!("  debug monitors [--raw|--diff]              Show the parsed monitor config, hyprctl's JSON,");
+    println!("                                             or what changed since monitors.json was cached");
+    println!("  debug drift                                Report where monitors.json, ws.conf and Hyprland disagree");
+    println!("  instances                                  List running Hyprland instances");
+    println!("  -y | --yes                                 Don't ask before overwriting ws.conf");
+    println!("  --read-only                                Switch and focus only: don't write ws.conf or monitors.json,");
+    println!("                                             reload Hyprland or change its settings");
+    println!("  --instance SIGNATURE|INDEX                 Target one Hyprland instance (per-instance ws.conf/cache)");
+    println!("  --config FILE                              Read settings from FILE (or $HYPRWS_CONFIG)");
+    println!("  --cache-dir DIR                            Keep monitors.json and thumbnails in DIR (or $HYPRWS_CACHE_DIR)");
+    println!();
+    println!("Workspace arguments:");
+    println!("  N                                          Group N");
+    println!("  +N | -N                                    N groups after/before the current one");
+    println!("  ~                                          Previous workspace on the current monitor");
+    println!("  first-empty                                First empty workspace on the current monitor");
+    println!("  m:N                                        Workspace N of the current monitor");
+    println!("  name:NAME | special[:NAME]                 A named or special workspace (-s and -m only)");
+    println!();
+    println!("Configuration Limits:");
+    println!("  Maximum workspaces: {}", MAX_WORKSPACES);
+    println!("  Maximum monitors: {}", MAX_MONITORS);
+    std::process::exit(1);
+}
+
+// The hyprws command line
+pub fn run() {
+    let mut args: Vec<String> = env::args().collect();
+
+    // --instance SIGNATURE|INDEX, --config FILE and --cache-dir DIR may
+    // appear anywhere on the command line
+    let mut context = Context::new();
+    for option in ["--instance", "--config", "--cache-dir"] {
+        let Some(position) = args.iter().position(|a| a == option) else {
+            continue;
+        };
+        if position + 1 >= args.len() {
+            display_help(&args[0]);
+        }
+        let value = args.drain(position..position + 2).nth(1).unwrap_or_default();
+        context = match option {
+            "--config" => context.config_file(&value),
+            "--cache-dir" => context.cache_dir(&value),
+            _ => context.instance(&value).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }),
+        };
+    }
+    // Still single-threaded here, and before the request socket is first used
+    if let Err(e) = context.activate() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    // A config file that doesn't parse stops every command but the ones that
+    // report on it or replace it
+    let repairs = matches!(args.get(1).map(String::as_str), Some("check" | "--check-config" | "migrate" | "config"));
+    let config = match config::load_or_default() {
+        Ok(config) => config,
+        Err(e) if repairs && e.kind() != std::io::ErrorKind::NotFound => {
+            eprintln!("Warning: couldn't load config: {}", e);
+            config::Config::default()
+        }
+        Err(e) => {
+            eprintln!("Error: couldn't load config: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let config_path = config.ws_conf_path(&instance_suffix());
+
+    // Expand a user-defined alias in place of the command
+    if args.len() > 1 {
+        if let Some(expansion) = config.aliases.get(&args[1]) {
+            let expanded: Vec<String> = expansion.split_whitespace().map(|s| s.to_string()).collect();
+            args.splice(1..2, expanded);
+        }
+    }
+
+    // --yes may appear anywhere on the command line
+    let assume_yes = args.iter().any(|a| a == "-y" || a == "--yes");
+    args.retain(|a| a != "-y" && a != "--yes");
+
+    // So may --read-only
+    context = context.read_only(args.iter().any(|a| a == "--read-only") || config.read_only);
+    args.retain(|a| a != "--read-only");
+    if let Err(e) = context.activate() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    if args.len() < 2 {
+        display_help(&args[0]);
+    }
+    if !groups::COMMANDS.contains(&args[1].as_str()) {
+        display_help(&args[0]);
+    }
+    audit::set_trigger(format!("cli: {}", args[1..].join(" ")));
+
+    match args[1].as_str() {
+        "-s" | "--workspace" | "-m" | "--move" | "move" => {
+            // A running daemon answers from its in-memory mapping
+            let result = control::forward(&args[1..]).unwrap_or_else(|| {
+                let maps = load_mapping(&config_path, &config);
+                workspace_command(&args[1..], &maps, &config)
+            });
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                display_help(&args[0]);
+            }
+        }
+        "--monitor" | "daemon" => {
+            // Get Hyprland socket
+            let socket = match monitor::get_hyprland_socket() {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            if args.len() > 2 {
+                // User provided scripts as arguments - use direct script execution
+                let script_attached = &args[2];
+                let script_detached = if args.len() > 3 {
+                    Some(args[3].as_str())
+                } else {
+                    None
+                };
+
+                // Call listen with scripts
+                let callback = |context: &monitor::MonitorContext| match context.event {
+                    MonitorEvent::Added => Action::RunScript(script_attached.clone()),
+                    MonitorEvent::Removed => match script_detached {
+                        Some(script) => Action::RunScript(script.to_string()),
+                        None => Action::Ignore,
+                    },
+                };
+                if let Err(e) = monitor::listen(socket, callback, || {}) {
+                    eprintln!("Error listening to Hyprland socket: {}", e);
+                    std::process::exit(1);
+                }
+            } else {
+                daemon::run(&config, &config_path, socket, assume_yes);
+            }
+        }
+        "assign" => {
+            let prompt = format!("This will overwrite {} and reload Hyprland. Continue?", config_path);
+            if !confirm(&prompt, assume_yes) {
+                std::process::exit(1);
+            }
+            let layout = profile::match_profile(&config, &get_connected_monitor_names());
+            match assign_workspaces(&config_path, layout, &config) {
+                Some(path) => {
+                    println!("Workspace configuration written to: {}", path);
+                    offer_install(&path);
+                }
+                None => {
+                    eprintln!("Failed to create workspace configuration");
+                    std::process::exit(1);
+                }
+            }
+        }
+        "profile" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let prompt = format!("This will apply profile '{}' and overwrite {}. Continue?", args[2], config_path);
+            if !confirm(&prompt, assume_yes) {
+                std::process::exit(1);
+            }
+            if let Err(e) = profile::apply_profile(&args[2], &config, &config_path) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            println!("Profile '{}' applied", args[2]);
+        }
+        "config" => {
+            if args.len() < 4 {
+                display_help(&args[0]);
+            }
+            let result = match args[2].as_str() {
+                "export" => bundle::export(&args[3], &config, &config_path),
+                "import" => bundle::import(&args[3], &config_path, |prompt| confirm(prompt, assume_yes)),
+                _ => display_help(&args[0]),
+            };
+            match result {
+                Ok(()) => println!("Bundle {} {}ed", args[3], args[2]),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "migrate" => {
+            if let Err(e) = migrate::migrate(&config_path, |prompt| confirm(prompt, assume_yes)) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "dropdown" => {
+            let name = args.get(2).map(|s| s.as_str()).unwrap_or("dropdown");
+            if let Err(e) = dropdown::toggle(name, &config) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "togglespecial" | "--toggle-special" => {
+            special::toggle(args.get(2).map(|s| s.as_str()));
+        }
+        "stash" => {
+            if let Err(e) = stash::stash() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "unstash" => {
+            let pick = args.get(2).is_some_and(|a| a == "--pick");
+            if let Err(e) = stash::unstash(pick, &config) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "goto" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let maps = load_mapping(&config_path, &config);
+            if let Err(e) = goto_window(&args[2], &maps) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "send-workspace" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let maps = load_mapping(&config_path, &config);
+            let swap = args.iter().skip(3).any(|a| a == "--swap");
+            if let Err(e) = send_workspace(&args[2], &maps, swap, &config) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "peek" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let duration = match args.iter().position(|a| a == "--duration") {
+                Some(i) => match args.get(i + 1).and_then(|d| peek::parse_duration(d)) {
+                    Some(duration) => Some(duration),
+                    None => display_help(&args[0]),
+                },
+                None => None,
+            };
+            let maps = load_mapping(&config_path, &config);
+            let result = target::resolve(&args[2], &maps, &config)
+                .and_then(|target| peek::peek(target.workspace, &maps, duration));
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "rotate" => {
+            let maps = load_mapping(&config_path, &config);
+            let reverse = args.iter().skip(2).any(|a| a == "--reverse");
+            if let Err(e) = rotate_workspaces(&maps, reverse) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "arrange" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let maps = load_mapping(&config_path, &config);
+            if let Err(e) = arrange::arrange(&args[2], &maps, &config) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "pin" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let Ok(group) = args[2].parse::<i32>() else {
+                eprintln!("Invalid group: {}", args[2]);
+                display_help(&args[0]);
+            };
+            let monitor = args
+                .iter()
+                .position(|a| a == "--monitor")
+                .and_then(|i| args.get(i + 1))
+                .cloned();
+            if let Err(e) = pin::pin(group, monitor) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "unpin" => {
+            if let Err(e) = pin::unpin() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "set-primary" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            let name = args[2].clone();
+            if !get_connected_monitor_names().contains(&name) {
+                eprintln!("Warning: {} isn't connected; it will be used once it is", name);
+            }
+            if let Err(e) = state::update(|s| s.primary = Some(name)) {
+                eprintln!("Error saving primary monitor: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "instances" => {
+            for (index, signature) in monitor::list_instances().iter().enumerate() {
+                println!("{}: {}", index, signature);
+            }
+        }
+        "--prev" | "prev" => {
+            let maps = load_mapping(&config_path, &config);
+            if let Err(e) = switch_to_previous_workspace(&maps, &config) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "focus-prev" => {
+            if let Err(e) = focus_previous_window() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "history" => {
+            if !args.iter().any(|a| a == "--dispatches") {
+                display_help(&args[0]);
+            }
+            audit::show(args.iter().any(|a| a == "--json"));
+        }
+        "usage" => {
+            let heatmap = args.iter().any(|a| a == "--heatmap");
+            let json = args.iter().any(|a| a == "--json");
+            usage::show(heatmap, json);
+        }
+        "--generate-module" => match nix::generate_module() {
+            Ok(module) => print!("{}", module),
+            Err(e) => {
+                eprintln!("Couldn't generate module: {}", e);
+                std::process::exit(1);
+            }
+        },
+        "check" | "--check-config" => {
+            if check::check(&config_path, &config) > 0 {
+                std::process::exit(1);
+            }
+        }
+        "import-kanshi" => {
+            if args.len() < 3 {
+                display_help(&args[0]);
+            }
+            match kanshi::import(&args[2]) {
+                Ok(settings) => print!("{}", settings),
+                Err(e) => {
+                    eprintln!("Couldn't import {}: {}", args[2], e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "install" => {
+            if let Err(e) = install::install(&config_path) {
+                eprintln!("Couldn't update hyprland.conf: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "status" => {
+            show_status(args.get(2).is_some_and(|a| a == "--json"));
+        }
+        "overview" => {
+            show_overview(args.get(2).is_some_and(|a| a == "--json"));
+        }
+        "list" => {
+            groups::list(args.get(2).is_some_and(|a| a == "--json"), &config);
+        }
+        "pick" => {
+            let Some(group) = groups::pick(&config) else {
+                return;
+            };
+            if let Err(e) = context.switch_group(group) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "completions" => match groups::completions(args.get(2).map_or("", String::as_str)) {
+            Ok(script) => print!("{}", script),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        "debug" if args.get(2).is_some_and(|a| a == "monitors") => {
+            let view = match args.get(3).map(|s| s.as_str()) {
+                Some("--raw") => debug::MonitorsView::Raw,
+                Some("--diff") => debug::MonitorsView::Diff,
+                _ => debug::MonitorsView::Parsed,
+            };
+            if let Err(e) = debug::monitors(view) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        "debug" if args.get(2).is_some_and(|a| a == "drift") => {
+            if debug::drift(&config_path) > 0 {
+                std::process::exit(1);
+            }
+        }
+        _ => display_help(&args[0]),
+    }
+}
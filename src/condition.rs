@@ -0,0 +1,376 @@
+use regex::Regex;
+use serde_json::Value;
+
+// A hook stage's `when` condition, e.g.
+//   event == 'monitoradded' && monitor.desc contains 'DELL'
+// Names are dotted paths into the context the daemon builds for the event;
+// strings are quoted with ' or ", and &&, ||, ! and parentheses combine
+// comparisons. A name on its own tests whether it's set and not false, 0
+// or empty.
+#[derive(Debug)]
+pub enum Condition {
+    Or(Box<Condition>, Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Compare(Operand, Op, Operand),
+    Matches(Operand, Regex),
+    Truthy(Operand),
+}
+
+#[derive(Debug)]
+pub enum Operand {
+    Literal(Value),
+    Path(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 11] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")"];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => string.extend(chars.next().map(|(_, c)| c)),
+                    Some((_, q)) if q == c => break,
+                    Some((_, other)) => string.push(other),
+                    None => return Err(format!("unterminated string starting at column {}", start + 1)),
+                }
+            }
+            tokens.push(Token::Text(string));
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| text[start..].starts_with(**s)) {
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push(Token::Symbol(symbol));
+        } else if c.is_ascii_digit() || c == '-' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.' || (i == start && c == '-')) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = text[start..end]
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", &text[start..end]))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(text[start..end].to_string()));
+        } else {
+            return Err(format!("unexpected '{}' at column {}", c, start + 1));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut condition = self.and()?;
+        while self.eat("||") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut condition = self.not()?;
+        while self.eat("&&") {
+            condition = Condition::And(Box::new(condition), Box::new(self.not()?));
+        }
+        Ok(condition)
+    }
+
+    fn not(&mut self) -> Result<Condition, String> {
+        if self.eat("!") {
+            return Ok(Condition::Not(Box::new(self.not()?)));
+        }
+        if self.eat("(") {
+            let condition = self.or()?;
+            if !self.eat(")") {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(condition);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Condition, String> {
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("==")) => Op::Eq,
+            Some(Token::Symbol("!=")) => Op::Ne,
+            Some(Token::Symbol("<")) => Op::Lt,
+            Some(Token::Symbol("<=")) => Op::Le,
+            Some(Token::Symbol(">")) => Op::Gt,
+            Some(Token::Symbol(">=")) => Op::Ge,
+            Some(Token::Word(w)) if w == "contains" => Op::Contains,
+            Some(Token::Word(w)) if w == "matches" => {
+                self.position += 1;
+                return match self.next() {
+                    Some(Token::Text(pattern)) => Regex::new(&pattern)
+                        .map(|regex| Condition::Matches(left, regex))
+                        .map_err(|e| e.to_string()),
+                    _ => Err("matches needs a quoted regular expression".to_string()),
+                };
+            }
+            _ => return Ok(Condition::Truthy(left)),
+        };
+        self.position += 1;
+        Ok(Condition::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Text(text)) => Ok(Operand::Literal(Value::String(text))),
+            Some(Token::Number(number)) => Ok(Operand::Literal(number.into())),
+            Some(Token::Word(word)) => Ok(match word.as_str() {
+                "true" => Operand::Literal(Value::Bool(true)),
+                "false" => Operand::Literal(Value::Bool(false)),
+                "null" => Operand::Literal(Value::Null),
+                "contains" | "matches" => return Err(format!("'{}' needs a value before it", word)),
+                _ => Operand::Path(word.split('.').map(|s| s.to_string()).collect()),
+            }),
+            Some(Token::Symbol(symbol)) => Err(format!("unexpected '{}'", symbol)),
+            None => Err("unexpected end of condition".to_string()),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Condition, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+    };
+    let condition = parser.or()?;
+    match parser.peek() {
+        None => Ok(condition),
+        Some(token) => Err(format!("unexpected {:?}", token)),
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, context: &'a Value) -> &'a Value {
+        match self {
+            Operand::Literal(value) => value,
+            Operand::Path(path) => path
+                .iter()
+                .try_fold(context, |value, key| match value {
+                    Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                    _ => value.get(key),
+                })
+                .unwrap_or(&Value::Null),
+        }
+    }
+}
+
+// Strings that look like numbers compare as numbers, so `data == 3` works
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn equal(left: &Value, right: &Value) -> bool {
+    match (number(left), number(right)) {
+        (Some(a), Some(b)) => a == b,
+        _ => left == right || text(left) == text(right),
+    }
+}
+
+impl Condition {
+    pub fn eval(&self, context: &Value) -> bool {
+        match self {
+            Condition::Or(a, b) => a.eval(context) || b.eval(context),
+            Condition::And(a, b) => a.eval(context) && b.eval(context),
+            Condition::Not(condition) => !condition.eval(context),
+            Condition::Truthy(operand) => truthy(operand.resolve(context)),
+            Condition::Matches(operand, regex) => regex.is_match(&text(operand.resolve(context))),
+            Condition::Compare(left, op, right) => {
+                let (left, right) = (left.resolve(context), right.resolve(context));
+                match op {
+                    Op::Eq => equal(left, right),
+                    Op::Ne => !equal(left, right),
+                    Op::Contains => match left {
+                        Value::Array(items) => items.iter().any(|item| equal(item, right)),
+                        Value::Object(fields) => fields.contains_key(&text(right)),
+                        _ => text(left).contains(&text(right)),
+                    },
+                    _ => match (number(left), number(right)) {
+                        (Some(a), Some(b)) => match op {
+                            Op::Lt => a < b,
+                            Op::Le => a <= b,
+                            Op::Gt => a > b,
+                            _ => a >= b,
+                        },
+                        _ => false,
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(condition: &str, context: &Value) -> bool {
+        parse(condition).unwrap_or_else(|e| panic!("'{}' didn't parse: {}", condition, e)).eval(context)
+    }
+
+    fn context() -> Value {
+        json!({
+            "event": "monitoradded",
+            "data": "3",
+            "monitor": {"name": "DP-1", "desc": "Dell Inc. DELL U2720Q", "count": 2},
+            "monitors": ["DP-1", "eDP-1"],
+        })
+    }
+
+    #[test]
+    fn compares_and_contains() {
+        let context = context();
+        assert!(check("event == 'monitoradded'", &context));
+        assert!(!check("event != 'monitoradded'", &context));
+        assert!(check("data == 3", &context));
+        assert!(check("monitor.count >= 2 && monitor.count < 3", &context));
+        assert!(check("monitor.desc contains 'DELL'", &context));
+        assert!(check("monitors contains 'eDP-1'", &context));
+        assert!(!check("monitors contains 'HDMI-A-1'", &context));
+        assert!(check("monitor.name matches '^DP-[0-9]$'", &context));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let context = context();
+        // true || (false && false)
+        assert!(check("event == 'monitoradded' || data == 4 && data == 5", &context));
+        // (false && true) || false
+        assert!(!check("data == 4 && event == 'monitoradded' || data == 5", &context));
+    }
+
+    #[test]
+    fn parentheses_group() {
+        let context = context();
+        assert!(!check("(event == 'monitoradded' || data == 4) && data == 5", &context));
+        assert!(check("!(data == 4 || data == 5)", &context));
+    }
+
+    #[test]
+    fn quoted_strings_keep_spaces() {
+        let context = context();
+        assert!(check("monitor.desc == 'Dell Inc. DELL U2720Q'", &context));
+        assert!(check("monitor.desc contains \"Inc. DELL\"", &context));
+        assert!(check("'it\\'s' == \"it's\"", &context));
+    }
+
+    #[test]
+    fn unknown_fields_are_null() {
+        let context = context();
+        assert!(!check("profile", &context));
+        assert!(check("!monitor.serial", &context));
+        assert!(!check("monitor.serial == 'X'", &context));
+        assert!(check("monitor.serial != 'X'", &context));
+        assert!(!check("workspace.id > 1", &context));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for condition in [
+            "",
+            "event ==",
+            "== 'x'",
+            "(event == 'x'",
+            "event == 'x')",
+            "'unterminated",
+            "event = 'x'",
+            "event matches x",
+            "event matches '('",
+            "contains 'x'",
+            "&& event",
+            "event 'x'",
+            "1.2.3 == 1",
+        ] {
+            assert!(parse(condition).is_err(), "'{}' should not parse", condition);
+        }
+    }
+}
@@ -0,0 +1,1157 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::condition::{self, Condition};
+use crate::{night_light, paths, webhook, MAX_WORKSPACES};
+
+// User settings read from ~/.config/hyprws/config.toml (or config.yaml / config.json)
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Maximum number of windows on a workspace before new windows overflow
+    // to the least-populated workspace of the same group
+    pub max_windows: Option<usize>,
+
+    // Number of workspaces created on each monitor (1-10)
+    pub workspaces_per_monitor: Option<usize>,
+
+    // Wallpaper daemon told about a profile's wallpapers when it's applied;
+    // none by default
+    pub wallpaper_backend: Option<WallpaperBackend>,
+
+    // Night light tool told about a profile's night_light settings when it's
+    // applied; none by default. hyprsunset and gammastep tint every monitor
+    // alike: when a profile's monitors differ, all get the warmest of their
+    // settings. night_light_command runs once per monitor with HYPRWS_MONITOR
+    // and HYPRWS_TEMPERATURE (Kelvin, or "off") set.
+    pub night_light_backend: Option<NightLightBackend>,
+    pub night_light_command: Option<String>,
+
+    // Generated rules file sourced from hyprland.conf (default ~/.config/hypr/ws.conf)
+    pub ws_conf: Option<String>,
+    // Generate each profile's rules into its own file next to ws_conf and
+    // reduce ws_conf to a `source =` line selecting the active one
+    pub profile_outputs: bool,
+    // Where switch/move read the workspace mapping from
+    pub mapping_source: MappingSource,
+    // What a switch does when the mapping has no workspace for the group
+    pub no_mapping: NoMappingPolicy,
+    // What switching to the group already focused does
+    pub repeat_switch: RepeatSwitch,
+    // Where the workspace block of a monitor not seen before goes; the block
+    // is then remembered for the monitor's description
+    pub placement: Placement,
+    // Blocks kept for placement = "reserved", given by their first
+    // workspace, e.g. [71, 81]
+    pub reserved_blocks: Vec<i32>,
+
+    // Default names for workspace groups, keyed by group number ("1" to "10")
+    pub names: HashMap<String, String>,
+    // What each group is for, keyed by group number, e.g. "3" = "browsers";
+    // shown by list, pick and shell completions
+    pub descriptions: HashMap<String, String>,
+
+    // Seconds a cached monitors.json stays usable (default 300)
+    pub monitor_cache_ttl: Option<u64>,
+
+    // Capture workspace thumbnails with grim for pickers (see overview --json)
+    pub thumbnails: bool,
+
+    // Warp the cursor to the center of the monitor a switch moves focus to
+    pub warp_cursor: bool,
+
+    // Where focus goes after move / move --silent
+    pub post_move_focus: FocusPolicy,
+
+    // Close open special workspaces when a switch changes groups
+    pub hide_specials_on_switch: bool,
+
+    // Show a notification with the workspace and monitor after a switch
+    pub osd: bool,
+    // How long the notification stays up, in milliseconds (default 1000)
+    pub osd_duration: Option<u64>,
+
+    // Keyboard layout index (into input:kb_layout) per workspace group, keyed
+    // by group number; "default" applies to groups not listed
+    pub layouts: HashMap<String, u32>,
+    // Keyboard passed to hyprctl switchxkblayout (default "all")
+    pub layout_device: Option<String>,
+
+    // general:col.active_border per workspace group, keyed by group number,
+    // e.g. "1" = "rgba(33ccffee) rgba(00ff99ee) 45deg"; "default" applies to
+    // groups not listed
+    pub accents: HashMap<String, String>,
+
+    // Groups that turn on the notification daemon's do-not-disturb while
+    // focused, e.g. [5]; it's restored when focus leaves them
+    pub dnd_groups: Vec<i32>,
+    // Notification daemon told about it; none by default
+    pub dnd_backend: Option<DndBackend>,
+
+    // Quake-style terminals toggled with `hyprws dropdown NAME`
+    pub dropdowns: HashMap<String, Dropdown>,
+
+    // dmenu-style picker reading choices on stdin (default "rofi -dmenu")
+    pub picker: Option<String>,
+
+    // Also watch udev drm/usb events in the daemon and retry monitor queries
+    // with backoff, for docks that bring displays up slowly
+    pub udev: bool,
+
+    // Watch systemd-logind for lid and resume events in the daemon
+    pub logind: bool,
+    pub lid: Lid,
+
+    // Ordered hook pipelines keyed by socket2 event name, e.g. [[hooks.monitoradded]]
+    pub hooks: HashMap<String, Vec<HookStage>>,
+    // Submaps during which hooks don't run; "*" means any but the default
+    pub suppress_hooks_in_submaps: Vec<String>,
+
+    // User units restarted (systemctl --user) and a shell command run after
+    // the daemon reassigns workspaces, e.g. bars that don't follow monitor
+    // changes; debounced by restart_debounce milliseconds (default 1000)
+    pub restart_units: Vec<String>,
+    pub restart_command: Option<String>,
+    pub restart_debounce: Option<u64>,
+
+    // Seconds the daemon may spend on one event before it counts as hung
+    // (default 120). Under systemd with WatchdogSec= its keepalives then
+    // stop, so the service is restarted; a long-running hook counts too.
+    pub watchdog_stall: Option<u64>,
+
+    // Loopback TCP remote control for the daemon
+    pub remote: Option<Remote>,
+
+    // HTTP endpoints the daemon POSTs events with a state snapshot to, e.g.
+    // Home Assistant webhooks ([[webhooks]])
+    pub webhooks: Vec<Webhook>,
+
+    // MQTT broker the daemon publishes per-monitor state to
+    pub mqtt: Option<Mqtt>,
+
+    // Before the daemon's first assignment, wait until monitors have been
+    // stable for this many milliseconds (for exec-once races; off by default)
+    pub startup_settle: Option<u64>,
+    // Longest the settle phase may take, in milliseconds (default 10000)
+    pub startup_timeout: Option<u64>,
+
+    // Milliseconds a removed monitor may stay away before workspaces are
+    // reassigned; if it returns in time its workspaces are moved back instead
+    pub disconnect_grace: Option<u64>,
+    // Treat an output removed by DPMS off like a disconnect (by default its
+    // workspaces stay put until it's powered on again)
+    pub reassign_on_dpms: bool,
+
+    // Enumerate monitors with wlr-randr when hyprctl queries fail
+    pub wayland_fallback: bool,
+
+    // Workspace groups (1-10) that automatic moves (max_windows overflow,
+    // pins, unmapped relocation) never take windows into or out of
+    pub protected_groups: Vec<i32>,
+
+    // Window classes (regular expressions) ignored when counting windows
+    pub exclude_classes: Vec<String>,
+
+    // Per-window weights used when picking the least-populated workspace
+    pub weights: Weights,
+
+    // Same as --read-only: for kiosk and demo sessions
+    pub read_only: bool,
+
+    // Command aliases expanded by the CLI, e.g. tv = "move --silent 1 --to-monitor HDMI-A-1"
+    pub aliases: HashMap<String, String>,
+
+    // What the daemon does with numbered workspaces created outside the
+    // generated mapping, e.g. by `hyprctl dispatch workspace 55`
+    pub unmapped_workspaces: UnmappedPolicy,
+
+    // Monitor roles usable wherever a monitor name is, mapped to (part of)
+    // the monitor's description, e.g. side = "Dell Inc. DELL U2419H"
+    pub roles: HashMap<String, String>,
+
+    // Monitor that receives workspaces left without a monitor of their own
+    // (`hyprws set-primary NAME` overrides it)
+    pub primary: Option<String>,
+
+    // Windows kept in a workspace group across monitor changes, e.g.
+    // [[pins]] class = "Slack", group = 3, monitor = "DP-1"
+    pub pins: Vec<ClassPin>,
+
+    // Named monitor/workspace arrangements applied with `hyprws profile NAME`
+    pub profiles: HashMap<String, Profile>,
+
+    // Fixed workspaces or mirroring for particular monitors, matched by
+    // connector, description or serial ([[outputs]])
+    pub outputs: Vec<OutputRule>,
+
+    // [host."NAME"] overrides; load() merges the matching one and drops the rest
+    host: HashMap<String, toml::Value>,
+
+    #[serde(skip)]
+    exclude_matchers: Vec<Regex>,
+}
+
+// [dropdowns.NAME] table
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Dropdown {
+    // Command that starts the terminal, e.g. "kitty --class dropdown"
+    pub command: String,
+    // Window class used to find the running terminal
+    pub class: String,
+    // Fraction of the focused monitor's height to cover
+    pub height: f32,
+}
+
+impl Default for Dropdown {
+    fn default() -> Self {
+        Dropdown {
+            command: String::new(),
+            class: String::new(),
+            height: 0.4,
+        }
+    }
+}
+
+// One stage of a [[hooks.EVENT]] pipeline: a shell command or a builtin step
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct HookStage {
+    pub command: Option<String>,
+    pub builtin: Option<Builtin>,
+    // Shell the command runs in with -c (default sh), e.g. "fish"
+    pub shell: Option<String>,
+    // Program and arguments the command is appended to as one argument,
+    // instead of a shell, e.g. ["python3"] with command = "~/bin/dock.py"
+    pub interpreter: Vec<String>,
+    // Working directory (default the daemon's) and extra environment
+    pub cwd: Option<String>,
+    pub env: HashMap<String, String>,
+    // Keep running later stages when this one fails
+    pub continue_on_error: bool,
+    // Run the stage only when this holds for the event, e.g.
+    // "event == 'monitoradded' && monitor.desc contains 'DELL'"
+    pub when: Option<String>,
+
+    #[serde(skip)]
+    pub condition: Option<Condition>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Builtin {
+    // hyprws's own workspace reassignment; placing it in a pipeline replaces
+    // the daemon's default reassignment for that event
+    Reassign,
+}
+
+// [[pins]] entry
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ClassPin {
+    // Window class (regular expression)
+    pub class: String,
+    pub group: i32,
+    pub monitor: Option<String>,
+
+    #[serde(skip)]
+    pub matcher: Option<Regex>,
+}
+
+// [remote] table
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Remote {
+    pub port: u16,
+    // Shared secret every request must carry
+    pub token: Option<String>,
+}
+
+// [mqtt] table
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Mqtt {
+    pub host: String,
+    // Default 1883 (TLS isn't supported)
+    pub port: Option<u16>,
+    // Topic prefix (default "hyprws"); state goes to PREFIX/monitor/NAME and
+    // PREFIX/status says whether the daemon is online
+    pub topic: Option<String>,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Mqtt {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(1883)
+    }
+
+    pub fn topic(&self) -> &str {
+        self.topic.as_deref().unwrap_or("hyprws").trim_end_matches('/')
+    }
+
+    pub fn client_id(&self) -> &str {
+        self.client_id.as_deref().unwrap_or("hyprws")
+    }
+}
+
+// [[webhooks]] entry
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    // http:// URL (TLS isn't supported)
+    pub url: String,
+    // socket2 events posted; workspace, monitor, special workspace and
+    // submap changes when empty
+    #[serde(default)]
+    pub events: Vec<String>,
+    // Extra request headers, e.g. Authorization
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    // Shortest gap between two posts in milliseconds (default 1000); events
+    // in between are coalesced into the latest one
+    pub min_interval: Option<u64>,
+    // Attempts after a failed post (default 3)
+    pub retries: Option<u32>,
+
+    #[serde(skip)]
+    pub endpoint: Option<webhook::Endpoint>,
+}
+
+impl Webhook {
+    pub fn min_interval(&self) -> u64 {
+        self.min_interval.unwrap_or(1000)
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(3)
+    }
+}
+
+// [lid] table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Lid {
+    // Name of the laptop panel, e.g. "eDP-1"
+    pub internal: Option<String>,
+    // Disable the panel while the lid is closed and an external monitor is connected
+    pub clamshell: bool,
+}
+
+// post_move_focus setting
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusPolicy {
+    // move follows the window's group, move --silent stays put
+    #[default]
+    Default,
+    // Focus stays on the current workspace
+    Stay,
+    // Focus follows the moved window
+    Follow,
+    // Focus returns to the previously focused window
+    Previous,
+}
+
+// mapping_source setting
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MappingSource {
+    // The generated ws.conf
+    #[default]
+    File,
+    // `hyprctl workspacerules -j`, i.e. whatever rules Hyprland has loaded
+    Hyprland,
+}
+
+// no_mapping setting
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoMappingPolicy {
+    // Report it and do nothing
+    #[default]
+    Fail,
+    // Work the mapping out from the connected monitors without writing it
+    Compute,
+    // Rewrite ws.conf, then switch with the new mapping
+    Reassign,
+    // Plain `hyprctl dispatch workspace N`
+    Dispatch,
+}
+
+// dnd_backend setting
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DndBackend {
+    // makoctl mode -a/-r do-not-disturb; mako's config needs a
+    // [mode=do-not-disturb] section, e.g. with invisible=1
+    Mako,
+    // swaync-client --dnd-on/--dnd-off
+    Swaync,
+}
+
+// repeat_switch setting
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RepeatSwitch {
+    // Focus the next monitor, cycling through the group's workspaces
+    #[default]
+    Cycle,
+    // Keep focus on the current monitor's workspace
+    Focus,
+}
+
+// placement setting
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    // After the blocks already in use
+    #[default]
+    Append,
+    // Between the monitors to its left and right, moving later blocks up
+    // when there's no room
+    Position,
+    // The first free block of reserved_blocks
+    Reserved,
+}
+
+// unmapped_workspaces setting
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnmappedPolicy {
+    // Leave them wherever Hyprland put them
+    #[default]
+    Ignore,
+    // Move the workspace to the monitor whose block its number falls in
+    // (the last block's monitor for numbers past the end)
+    Adopt,
+    // Send it and its windows to the same group's workspace on the focused
+    // monitor
+    Relocate,
+}
+
+// [weights] table, e.g. fullscreen = 3.0, floating = 0.5
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub tiled: f32,
+    pub floating: f32,
+    pub fullscreen: f32,
+}
+
+// [profiles.NAME] table
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    // Monitors in the order their workspace blocks are assigned; empty means
+    // all connected monitors in ID order. The daemon switches to the profile
+    // automatically when exactly these monitors are connected.
+    pub monitors: Vec<String>,
+
+    // Values passed to `hyprctl keyword monitor`, e.g. "DP-1,2560x1440@144,0x0,1"
+    pub monitor_keywords: Vec<String>,
+
+    // Shell commands run once the profile has been applied, and when
+    // another profile replaces it; HYPRWS_PROFILE holds the profile name
+    pub on_enter: Vec<String>,
+    pub on_exit: Vec<String>,
+
+    // Power profile set with powerprofilesctl when the profile is applied,
+    // e.g. "performance" when docked, "power-saver" when mobile
+    pub power_profile: Option<String>,
+
+    // Wallpaper per monitor (or role), shown through wallpaper_backend
+    pub wallpapers: HashMap<String, String>,
+
+    // Color temperature per monitor (or role) set through
+    // night_light_backend: Kelvin, e.g. "4000", or "off"
+    pub night_light: HashMap<String, String>,
+}
+
+// [[outputs]] entry, e.g. description = "DELL U2720Q", workspaces = "1-10"
+// or name = "HDMI-A-1", mirror = "eDP-1". Every matcher given must match.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputRule {
+    // Connector name
+    pub name: Option<String>,
+    // Part of the monitor's description
+    pub description: Option<String>,
+    // Serial number as Hyprland reports it
+    pub serial: Option<String>,
+    // Workspaces the monitor gets, "FIRST-LAST" within one block of ten,
+    // e.g. "11-15"
+    pub workspaces: Option<String>,
+    // Monitor (or role) this one mirrors; it gets no workspaces
+    pub mirror: Option<String>,
+}
+
+impl OutputRule {
+    pub fn matches(&self, name: &str, description: &str, serial: &str) -> bool {
+        (self.name.is_some() || self.description.is_some() || self.serial.is_some())
+            && self.name.as_ref().is_none_or(|n| n == name)
+            && self.description.as_ref().is_none_or(|d| description.contains(d.as_str()))
+            && self.serial.as_ref().is_none_or(|s| s == serial)
+    }
+
+    // (first, last) workspace of the workspaces setting
+    pub fn range(&self) -> Option<(usize, usize)> {
+        parse_range(self.workspaces.as_deref()?).ok()
+    }
+}
+
+// "11-15" or a single "11"; both ends in the same block of ten
+fn parse_range(text: &str) -> Result<(usize, usize), String> {
+    let (first, last) = text.split_once('-').unwrap_or((text, text));
+    let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| format!("invalid workspace '{}'", n.trim()));
+    let (first, last) = (parse(first)?, parse(last)?);
+    if first == 0 || first > last {
+        return Err(format!("'{}' isn't a range of workspaces", text));
+    }
+    if last > MAX_WORKSPACES {
+        return Err(format!("workspaces only go up to {}", MAX_WORKSPACES));
+    }
+    if (first - 1) / 10 != (last - 1) / 10 {
+        return Err(format!("'{}' spans two blocks of ten; groups are workspace % 10", text));
+    }
+    Ok((first, last))
+}
+
+// wallpaper_backend setting
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WallpaperBackend {
+    // hyprctl hyprpaper preload/wallpaper
+    Hyprpaper,
+    // swww img --outputs
+    Swww,
+}
+
+// night_light_backend setting
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NightLightBackend {
+    // hyprctl hyprsunset temperature/identity
+    Hyprsunset,
+    // gammastep -O, restarted for each profile
+    Gammastep,
+    // night_light_command
+    Command,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            tiled: 1.0,
+            floating: 1.0,
+            fullscreen: 1.0,
+        }
+    }
+}
+
+impl Config {
+    // Load the configuration from the file
+    pub fn load() -> io::Result<Self> {
+        let path = existing_config_file()?;
+        let contents = fs::read_to_string(&path)?;
+        let format = Format::from_path(&path);
+
+        // Deserialize the file as written first so errors carry a location
+        format.validate(&path, &contents)?;
+        let mut table = format.parse(&path, &contents)?;
+
+        // Merge the [host."NAME"] section for this machine over the base settings
+        if let Some(toml::Value::Table(mut hosts)) = table.remove("host") {
+            if let Some(toml::Value::Table(overrides)) = get_hostname().and_then(|h| hosts.remove(&h)) {
+                merge_tables(&mut table, overrides);
+            }
+        }
+
+        let mut config: Config = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| {
+                let (message, offset) = toml_error(&e);
+                ConfigError::new(&path, &contents, &message, offset)
+            })?;
+        config.compile_matchers()?;
+
+        Ok(config)
+    }
+
+    // Checked at load time; configs built in code are still kept in range
+    pub fn workspaces_per_monitor(&self) -> usize {
+        self.workspaces_per_monitor.unwrap_or(10).clamp(1, 10)
+    }
+
+    pub fn monitor_cache_ttl(&self) -> u64 {
+        self.monitor_cache_ttl.unwrap_or(300)
+    }
+
+    pub fn osd_duration(&self) -> u64 {
+        self.osd_duration.unwrap_or(1000)
+    }
+
+    pub fn startup_timeout(&self) -> u64 {
+        self.startup_timeout.unwrap_or(10000)
+    }
+
+    pub fn picker(&self) -> &str {
+        self.picker.as_deref().unwrap_or("rofi -dmenu")
+    }
+
+    pub fn layout_device(&self) -> &str {
+        self.layout_device.as_deref().unwrap_or("all")
+    }
+
+    // ws_conf with ~ expanded and the instance suffix before the extension
+    pub fn ws_conf_path(&self, suffix: &str) -> String {
+        let path = match &self.ws_conf {
+            Some(path) => paths::expand_home(path),
+            None => format!("{}/ws.conf", paths::hypr_dir()),
+        };
+        match path.strip_suffix(".conf") {
+            Some(stem) => format!("{}{}.conf", stem, suffix),
+            None => format!("{}{}", path, suffix),
+        }
+    }
+
+    pub fn watchdog_stall(&self) -> u64 {
+        self.watchdog_stall.unwrap_or(120)
+    }
+
+    pub fn restart_debounce(&self) -> u64 {
+        self.restart_debounce.unwrap_or(1000)
+    }
+
+    pub fn has_restarts(&self) -> bool {
+        !self.restart_units.is_empty() || self.restart_command.is_some()
+    }
+
+    pub fn suppresses_hooks(&self, submap: &str) -> bool {
+        !submap.is_empty()
+            && self.suppress_hooks_in_submaps.iter().any(|s| s == "*" || s == submap)
+    }
+
+    // Keyboard layout for a numbered workspace
+    pub fn layout_for(&self, workspace: i32) -> Option<u32> {
+        if workspace <= 0 {
+            return None;
+        }
+        let group = (workspace - 1) % 10 + 1;
+        self.layouts
+            .get(&group.to_string())
+            .or_else(|| self.layouts.get("default"))
+            .copied()
+    }
+
+    // Reserved blocks as indexes (workspace / 10)
+    pub fn reserved_block_indexes(&self) -> Vec<usize> {
+        self.reserved_blocks
+            .iter()
+            .filter(|ws| **ws > 0)
+            .map(|ws| (*ws as usize - 1) / 10)
+            .collect()
+    }
+
+    pub fn is_protected(&self, workspace: i32) -> bool {
+        workspace > 0 && self.protected_groups.contains(&((workspace - 1) % 10 + 1))
+    }
+
+    pub fn is_dnd_group(&self, workspace: i32) -> bool {
+        workspace > 0 && self.dnd_groups.contains(&((workspace - 1) % 10 + 1))
+    }
+
+    // Border color for a numbered workspace
+    pub fn accent_for(&self, workspace: i32) -> Option<&str> {
+        if workspace <= 0 {
+            return None;
+        }
+        let group = (workspace - 1) % 10 + 1;
+        self.accents
+            .get(&group.to_string())
+            .or_else(|| self.accents.get("default"))
+            .map(String::as_str)
+    }
+
+    pub fn description_for(&self, group: i32) -> Option<&str> {
+        self.descriptions.get(&group.to_string()).map(String::as_str)
+    }
+
+    // Compile exclude_classes into anchored regular expressions
+    fn compile_matchers(&mut self) -> io::Result<()> {
+        self.exclude_matchers = self.exclude_classes
+            .iter()
+            .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+            .collect::<Result<_, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        for (index, pin) in self.pins.iter_mut().enumerate() {
+            let matcher = Regex::new(&format!("^(?:{})$", pin.class)).map_err(|e| {
+                let message = format!("[[pins]] entry {}: class: {}", index + 1, e);
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            })?;
+            pin.matcher = Some(matcher);
+        }
+
+        let weights = [
+            ("tiled", self.weights.tiled),
+            ("floating", self.weights.floating),
+            ("fullscreen", self.weights.fullscreen),
+        ];
+        if let Some((name, weight)) = weights.iter().find(|(_, w)| !(w.is_finite() && *w > 0.0)) {
+            let message = format!("[weights] {} must be a positive number, got {}", name, weight);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        if let Some(count) = self.workspaces_per_monitor.filter(|n| !(1..=10).contains(n)) {
+            let message = format!("workspaces_per_monitor must be between 1 and 10, got {}", count);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        for (index, output) in self.outputs.iter().enumerate() {
+            let invalid = |message: String| {
+                let message = format!("[[outputs]] entry {}: {}", index + 1, message);
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            };
+            if output.name.is_none() && output.description.is_none() && output.serial.is_none() {
+                return Err(invalid("needs name, description or serial".to_string()));
+            }
+            if let Some(workspaces) = &output.workspaces {
+                parse_range(workspaces).map_err(invalid)?;
+            }
+        }
+
+        for (name, profile) in &self.profiles {
+            for (monitor, value) in &profile.night_light {
+                night_light::parse(value).map_err(|e| {
+                    let message = format!("[profiles.{}] night_light.{}: {}", name, monitor, e);
+                    io::Error::new(io::ErrorKind::InvalidData, message)
+                })?;
+            }
+        }
+        if self.night_light_backend == Some(NightLightBackend::Command) && self.night_light_command.is_none() {
+            let message = "night_light_backend = \"command\" needs night_light_command";
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        for (index, webhook) in self.webhooks.iter_mut().enumerate() {
+            let endpoint = webhook::parse_url(&webhook.url).map_err(|e| {
+                let message = format!("[[webhooks]] entry {}: {}", index + 1, e);
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            })?;
+            webhook.endpoint = Some(endpoint);
+        }
+
+        for (event, pipeline) in &mut self.hooks {
+            for (index, stage) in pipeline.iter_mut().enumerate() {
+                if let Some(when) = &stage.when {
+                    let condition = condition::parse(when).map_err(|e| {
+                        let message = format!("[hooks.{}] stage {}: when: {}", event, index + 1, e);
+                        io::Error::new(io::ErrorKind::InvalidData, message)
+                    })?;
+                    stage.condition = Some(condition);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Whether windows of this class are left out of occupancy counts
+    pub fn is_excluded(&self, class: &str) -> bool {
+        self.exclude_matchers.iter().any(|m| m.is_match(class))
+    }
+}
+
+// A config.toml error located in the file, with a suggestion when the
+// offending key or value looks like a typo of a known one
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub key: Option<String>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl ConfigError {
+    fn new(path: &str, contents: &str, message: &str, offset: Option<usize>) -> Self {
+        let message = message.trim().replace('\n', "; ");
+        let quoted = backticked(&message);
+
+        // Unknown fields and variants name the culprit first and the valid
+        // choices after it (YAML prefixes the path, e.g. "lid: unknown field")
+        let unknown = message.contains("unknown field") || message.contains("unknown variant");
+        let suggestion = if unknown {
+            quoted.first().and_then(|word| closest(word, &quoted[1..]))
+        } else {
+            None
+        };
+        // The full list of choices is noise once there's a likely match
+        let message = match (&suggestion, message.split_once(", expected")) {
+            (Some(_), Some((head, _))) => head.to_string(),
+            _ => message,
+        };
+
+        let offset = offset.or_else(|| {
+            // Errors from the merged [host] overrides lose their span; find
+            // the unknown key in the file instead
+            quoted.first().filter(|_| unknown).and_then(|key| find_key(contents, key))
+        });
+        let (line, column) = match offset {
+            Some(offset) => {
+                let (line, column) = line_column(contents, offset);
+                (Some(line), Some(column))
+            }
+            None => (None, None),
+        };
+        let key = offset.and_then(|offset| key_at(contents, offset));
+
+        ConfigError {
+            path: path.to_string(),
+            line,
+            column,
+            key,
+            message,
+            suggestion,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(key) = self.key.as_ref().filter(|k| !self.message.contains(&format!("`{}`", k))) {
+            write!(f, " (at `{}`)", key)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean {}?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<ConfigError> for io::Error {
+    fn from(error: ConfigError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+// Words quoted in `backticks` in a serde message, in order
+fn backticked(message: &str) -> Vec<String> {
+    message
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(str::to_string)
+        .collect()
+}
+
+// Known name closest to a misspelled one, if it's close enough to be a typo
+fn closest(word: &str, candidates: &[String]) -> Option<String> {
+    let limit = (word.len() / 3).max(2);
+    candidates
+        .iter()
+        .map(|c| (edit_distance(word, c), c))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// 1-based line and column of a byte offset
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+// Byte offset of the first line assigning the key, in any of the formats
+fn find_key(contents: &str, key: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let unquoted = trimmed.strip_prefix('"').unwrap_or(trimmed);
+        if let Some(rest) = unquoted.strip_prefix(key) {
+            let rest = rest.strip_prefix('"').unwrap_or(rest);
+            if rest.trim_start().starts_with(['=', '.', ':']) {
+                return Some(offset + line.len() - trimmed.len());
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// Key assigned on the line containing the offset, e.g. "max_windows"
+fn key_at(contents: &str, offset: usize) -> Option<String> {
+    let start = contents[..offset.min(contents.len())].rfind('\n').map_or(0, |i| i + 1);
+    let line = contents[start..].lines().next()?;
+    let (key, _) = line.split_once(['=', ':'])?;
+    let key = key.trim().trim_start_matches(['{', ',', '-', ' ']).trim_matches('"');
+    (!key.is_empty() && !key.starts_with('[')).then(|| key.to_string())
+}
+
+// Config file formats, picked by extension; all share the same model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Toml,
+    Yaml,
+    Json,
+}
+
+// Candidate config files, in order of precedence
+pub const CONFIG_FILES: [&str; 4] = ["config.toml", "config.yaml", "config.yml", "config.json"];
+
+// The config file given with --config / HYPRWS_CONFIG if it exists, or else
+// the first config file that exists in the config directory
+pub fn config_file_path() -> Option<String> {
+    if let Some(path) = paths::config_override() {
+        return Path::new(&path).exists().then_some(path);
+    }
+    CONFIG_FILES
+        .iter()
+        .map(|name| format!("{}/{}", paths::config_dir(), name))
+        .find(|path| Path::new(path).exists())
+}
+
+fn existing_config_file() -> io::Result<String> {
+    config_file_path().ok_or_else(|| {
+        let message = match paths::config_override() {
+            Some(path) => format!("{} doesn't exist", path),
+            None => format!("No config file in {}", paths::config_dir()),
+        };
+        io::Error::new(io::ErrorKind::NotFound, message)
+    })
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Format::Yaml,
+            Some("json") => Format::Json,
+            _ => Format::Toml,
+        }
+    }
+
+    // Deserialize straight into Config, which keeps the parser's location
+    fn validate(self, path: &str, contents: &str) -> io::Result<()> {
+        let error = match self {
+            Format::Toml => toml::from_str::<Config>(contents).err().map(|e| toml_error(&e)),
+            Format::Yaml => serde_yaml::from_str::<Config>(contents).err().map(|e| yaml_error(&e)),
+            Format::Json => serde_json::from_str::<Config>(contents).err().map(|e| json_error(&e, contents)),
+        };
+        match error {
+            Some((message, offset)) => Err(ConfigError::new(path, contents, &message, offset).into()),
+            None => Ok(()),
+        }
+    }
+
+    // Parse into a TOML table so [host] merging works the same for every format
+    fn parse(self, path: &str, contents: &str) -> io::Result<toml::Table> {
+        let fail = |(message, offset): (String, Option<usize>)| {
+            io::Error::from(ConfigError::new(path, contents, &message, offset))
+        };
+        let value = match self {
+            Format::Toml => toml::Value::Table(toml::from_str(contents).map_err(|e| fail(toml_error(&e)))?),
+            Format::Yaml => {
+                let value: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| fail(yaml_error(&e)))?;
+                toml::Value::try_from(value).map_err(|e| fail((e.to_string(), None)))?
+            }
+            Format::Json => {
+                let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| fail(json_error(&e, contents)))?;
+                toml::Value::try_from(value).map_err(|e| fail((e.to_string(), None)))?
+            }
+        };
+        match value {
+            toml::Value::Table(table) => Ok(table),
+            _ => Err(fail(("top level must be a mapping of settings".to_string(), None))),
+        }
+    }
+}
+
+// Parser errors as a message and byte offset. serde_yaml and serde_json
+// append the location to their messages; it's reported separately.
+fn toml_error(error: &toml::de::Error) -> (String, Option<usize>) {
+    (error.message().to_string(), error.span().map(|span| span.start))
+}
+
+fn yaml_error(error: &serde_yaml::Error) -> (String, Option<usize>) {
+    (strip_location(error.to_string()), error.location().map(|l| l.index()))
+}
+
+fn json_error(error: &serde_json::Error, contents: &str) -> (String, Option<usize>) {
+    (strip_location(error.to_string()), offset_of(contents, error.line(), error.column()))
+}
+
+fn strip_location(message: String) -> String {
+    match message.rsplit_once(" at line ") {
+        Some((head, _)) => head.to_string(),
+        None => message,
+    }
+}
+
+// Byte offset of a 1-based line and column
+fn offset_of(contents: &str, line: usize, column: usize) -> Option<usize> {
+    if line == 0 {
+        return None;
+    }
+    let start: usize = contents.split_inclusive('\n').take(line - 1).map(str::len).sum();
+    Some(start + column.saturating_sub(1))
+}
+
+fn get_hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+// Recursively merge overrides into base; non-table values are replaced
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(override_table)) => {
+                merge_tables(base_table, override_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// The config file's settings as written, [host] sections included
+pub fn raw_table() -> io::Result<toml::Table> {
+    let path = existing_config_file()?;
+    let contents = fs::read_to_string(&path)?;
+    let format = Format::from_path(&path);
+    format.validate(&path, &contents)?;
+    format.parse(&path, &contents)
+}
+
+// The config file's settings, or the defaults when there's no config file.
+// A file named with --config has to exist.
+pub fn load_or_default() -> io::Result<Config> {
+    match Config::load() {
+        Err(e) if e.kind() == io::ErrorKind::NotFound && paths::config_override().is_none() => Ok(Config::default()),
+        result => result,
+    }
+}
+
+// Helper function to get the config, falling back to defaults without a
+// config file. One that can't be read or parsed is reported and ends the
+// process rather than being silently replaced by the defaults.
+pub fn get_config() -> Config {
+    load_or_default().unwrap_or_else(|e| {
+        eprintln!("Error: couldn't load config: {}", e);
+        std::process::exit(1);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_workspace_ranges() {
+        assert_eq!(parse_range("11-15"), Ok((11, 15)));
+        assert_eq!(parse_range(" 3 - 3 "), Ok((3, 3)));
+        assert_eq!(parse_range("100"), Ok((100, 100)));
+        for invalid in ["", "0", "5-3", "9-12", "101", "a-b", "1-"] {
+            assert!(parse_range(invalid).is_err(), "'{}' should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_pin_patterns() {
+        let mut config: Config = toml::from_str("[[pins]]\nclass = \"Slack(\"\ngroup = 3\n").unwrap();
+        let error = config.compile_matchers().unwrap_err();
+        assert!(error.to_string().starts_with("[[pins]] entry 1: class: "), "{}", error);
+
+        let mut config: Config = toml::from_str("[[pins]]\nclass = \"Slack|discord\"\ngroup = 3\n").unwrap();
+        config.compile_matchers().unwrap();
+        assert!(config.pins[0].matcher.as_ref().is_some_and(|m| m.is_match("discord") && !m.is_match("Slackware")));
+    }
+
+    #[test]
+    fn rejects_non_positive_weights() {
+        for weights in ["fullscreen = 0.0", "floating = -1.0", "tiled = nan"] {
+            let mut config: Config = toml::from_str(&format!("[weights]\n{}\n", weights)).unwrap();
+            assert!(config.compile_matchers().is_err(), "{} should be rejected", weights);
+        }
+        let mut config: Config = toml::from_str("[weights]\nfloating = 0.5\n").unwrap();
+        config.compile_matchers().unwrap();
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("osd", ""), 3);
+        assert_eq!(edit_distance("osd", "osd"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("profiles", "profile"), 1);
+    }
+
+    #[test]
+    fn suggests_only_close_names() {
+        let candidates: Vec<String> = ["osd", "osd_duration", "picker"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(closest("osd_durration", &candidates), Some("osd_duration".to_string()));
+        assert_eq!(closest("pikcer", &candidates), Some("picker".to_string()));
+        assert_eq!(closest("workspaces", &candidates), None);
+    }
+
+    fn config_error(contents: &str) -> ConfigError {
+        let error = Format::Toml.validate("config.toml", contents).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = error.into_inner().unwrap().downcast::<ConfigError>().unwrap();
+        *error
+    }
+
+    #[test]
+    fn locates_unknown_keys_and_suggests_a_fix() {
+        let error = config_error("osd = true\nosd_durration = 500\n");
+        assert_eq!((error.line, error.column), (Some(2), Some(1)));
+        assert_eq!(error.suggestion.as_deref(), Some("osd_duration"));
+        let message = error.to_string();
+        assert!(message.starts_with("config.toml:2:1: "), "{}", message);
+        assert!(message.ends_with("; did you mean osd_duration?"), "{}", message);
+    }
+
+    #[test]
+    fn reports_type_errors_without_a_suggestion() {
+        let error = config_error("osd = true\nosd_duration = \"long\"\n");
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.suggestion, None);
+        assert!(!error.to_string().contains("did you mean"));
+    }
+}
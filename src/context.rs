@@ -0,0 +1,173 @@
+use std::io;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::{monitor, paths, MonitorConfig, WorkspaceMonitorMap, READ_ONLY};
+
+/// Context in effect for this process, set by the first Context::activate
+static ACTIVE: OnceLock<Context> = OnceLock::new();
+
+// The Hyprland instance, config file and cache directory hyprws works with,
+// and whether it may write files and change Hyprland's settings. The CLI
+// builds one from --instance, --config, --cache-dir and --read-only. Sockets
+// and caches are set up once per process, so once a context is active,
+// another one naming a different instance or file is refused.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Context {
+    instance: Option<String>,
+    config_file: Option<String>,
+    cache_dir: Option<String>,
+    read_only: bool,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Context::default()
+    }
+
+    // Target one Hyprland instance, by signature or by index into
+    // `hyprws instances`
+    pub fn instance(mut self, selector: &str) -> Result<Self, String> {
+        let instances = monitor::list_instances();
+        let signature = match selector.parse::<usize>() {
+            Ok(index) => instances
+                .get(index)
+                .cloned()
+                .ok_or_else(|| format!("No Hyprland instance with index {}", index))?,
+            Err(_) if instances.iter().any(|i| i == selector) => selector.to_string(),
+            Err(_) => return Err(format!("No Hyprland instance '{}'", selector)),
+        };
+        self.instance = Some(signature);
+        Ok(self)
+    }
+
+    // Read settings from this file instead of searching the config directory
+    pub fn config_file(mut self, path: &str) -> Self {
+        self.config_file = Some(paths::expand_home(path));
+        self
+    }
+
+    // Keep monitors.json and thumbnails here
+    pub fn cache_dir(mut self, path: &str) -> Self {
+        self.cache_dir = Some(paths::expand_home(path));
+        self
+    }
+
+    // Switch and focus only: leave ws.conf, monitors.json and Hyprland's
+    // settings alone
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    // Make this the process's context; every method below does so first
+    pub fn activate(&self) -> Result<(), String> {
+        if ACTIVE.get_or_init(|| self.clone()).conflicts(self) {
+            return Err("hyprws is already set up for another instance, config file or cache directory".to_string());
+        }
+        // Once read-only, always read-only
+        if self.read_only {
+            READ_ONLY.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    // Whether the two name a different instance, config file or cache
+    // directory; read-only can be added at any time
+    fn conflicts(&self, other: &Context) -> bool {
+        (&self.instance, &self.config_file, &self.cache_dir) != (&other.instance, &other.config_file, &other.cache_dir)
+    }
+
+    // Activate, also honouring read_only = true in the config
+    fn enter(&self, config: &Config) -> Result<(), String> {
+        self.activate()?;
+        if config.read_only {
+            READ_ONLY.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    // The config file's settings; NotFound without one
+    pub fn config(&self) -> io::Result<Config> {
+        self.activate().map_err(io::Error::other)?;
+        Config::load()
+    }
+
+    // The generated rules file of this context's instance
+    pub fn ws_conf_path(&self, config: &Config) -> String {
+        let suffix = self.instance.as_ref().map(|sig| format!("-{}", sig)).unwrap_or_default();
+        config.ws_conf_path(&suffix)
+    }
+
+    // Connected monitors, from the cache while it's fresh
+    pub fn monitor_config(&self, config: &Config) -> Result<MonitorConfig, String> {
+        self.enter(config)?;
+        Ok(crate::get_monitor_config(config))
+    }
+
+    // The workspace mapping hyprws switches by
+    pub fn load_mapping(&self, config: &Config) -> Result<Vec<WorkspaceMonitorMap>, String> {
+        self.enter(config)?;
+        Ok(crate::load_mapping(&self.ws_conf_path(config), config))
+    }
+
+    // Regenerate the rules for the connected monitors; `layout` names the
+    // profile they're for. Returns the file written, if any.
+    pub fn assign_workspaces(&self, layout: Option<&str>, config: &Config) -> Result<Option<String>, String> {
+        self.enter(config)?;
+        Ok(crate::assign_workspaces(&self.ws_conf_path(config), layout, config))
+    }
+
+    // Switch to a workspace group like `hyprws -s N`, through the daemon when
+    // one is running
+    pub fn switch_group(&self, group: i32) -> Result<(), String> {
+        self.activate()?;
+        crate::switch_group(group)
+    }
+}
+
+// --instance of the active context
+pub fn instance() -> Option<&'static str> {
+    ACTIVE.get()?.instance.as_deref()
+}
+
+// --config of the active context
+pub fn config_file() -> Option<&'static str> {
+    ACTIVE.get()?.config_file.as_deref()
+}
+
+// --cache-dir of the active context
+pub fn cache_dir() -> Option<&'static str> {
+    ACTIVE.get()?.cache_dir.as_deref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ws_conf_is_per_instance() {
+        let config = Config::default();
+        let context = Context {
+            instance: Some("abc".to_string()),
+            ..Context::new()
+        };
+        assert!(context.ws_conf_path(&config).ends_with("/ws-abc.conf"));
+        assert!(Context::new().ws_conf_path(&config).ends_with("/ws.conf"));
+    }
+
+    #[test]
+    fn conflicts_with_another_target() {
+        let context = Context::new().config_file("/etc/hyprws.toml").cache_dir("/tmp/cache");
+        assert!(!context.conflicts(&context.clone()));
+        assert!(!context.conflicts(&context.clone().read_only(true)));
+        assert!(context.conflicts(&Context::new().config_file("/etc/other.toml").cache_dir("/tmp/cache")));
+        assert!(context.conflicts(&Context::new()));
+        let instance = Context {
+            instance: Some("abc".to_string()),
+            ..context.clone()
+        };
+        assert!(context.conflicts(&instance));
+    }
+}
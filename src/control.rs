@@ -0,0 +1,100 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::time::Duration;
+
+use crate::{audit, create_runtime_dir, instance_suffix, runtime_path};
+
+// Longest a forwarded command may take before the CLI gives up on the
+// daemon, and a request may take to arrive
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Largest request read; a command line is far shorter
+const MAX_REQUEST: u64 = 64 * 1024;
+
+// The daemon's control socket in the runtime directory
+fn socket_path() -> String {
+    runtime_path(&format!("control{}.sock", instance_suffix()))
+}
+
+// Send a command to the daemon: the arguments separated by NUL, answered
+// with "ok" or "error MESSAGE". None when no daemon is listening, so the
+// caller runs the command itself.
+pub fn forward(args: &[String]) -> Option<Result<(), String>> {
+    let mut stream = UnixStream::connect(socket_path()).ok()?;
+    let send = |stream: &mut UnixStream| -> io::Result<String> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.write_all(args.join("\0").as_bytes())?;
+        stream.shutdown(Shutdown::Write)?;
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply)?;
+        Ok(reply)
+    };
+    let reply = match send(&mut stream) {
+        Ok(reply) => reply,
+        Err(e) => return Some(Err(format!("The daemon didn't answer: {}", e))),
+    };
+    let reply = reply.trim_end();
+    match reply.strip_prefix("error ") {
+        _ if reply == "ok" => Some(Ok(())),
+        Some(message) => Some(Err(message.to_string())),
+        None => Some(Err(format!("Unexpected reply from the daemon: '{}'", reply))),
+    }
+}
+
+fn answer<F>(stream: UnixStream, run: &F) -> io::Result<()>
+where
+    F: Fn(&[String]) -> Result<(), String>,
+{
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut request = Vec::new();
+    (&stream).take(MAX_REQUEST).read_to_end(&mut request)?;
+    let args: Vec<String> = String::from_utf8_lossy(&request)
+        .split('\0')
+        .map(|s| s.to_string())
+        .collect();
+
+    audit::set_trigger(format!("control: {}", args.join(" ")));
+    let reply = match run(&args) {
+        Ok(()) => "ok\n".to_string(),
+        Err(e) => format!("error {}\n", e.replace('\n', " ")),
+    };
+    (&stream).write_all(reply.as_bytes())
+}
+
+// The daemon's control server: run each forwarded command, one at a time
+// as keybinds come in. Another daemon already listening keeps the socket.
+pub fn serve<F>(run: F)
+where
+    F: Fn(&[String]) -> Result<(), String>,
+{
+    let path = socket_path();
+    if UnixStream::connect(&path).is_ok() {
+        eprintln!("Warning: another daemon is listening on {}; not serving commands", path);
+        return;
+    }
+    let bound = create_runtime_dir().and_then(|_| {
+        // Left behind by a daemon that didn't exit cleanly
+        let _ = fs::remove_file(&path);
+        UnixListener::bind(&path)
+    });
+    let listener = match bound {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Couldn't open control socket {}: {}", path, e);
+            return;
+        }
+    };
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = answer(stream, &run) {
+                    eprintln!("Control request failed: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Control connection failed: {}", e),
+        }
+    }
+}
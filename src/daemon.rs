@@ -0,0 +1,571 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{mpsc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, UnmappedPolicy};
+use crate::monitor::{self, Action, MonitorEvent};
+use crate::occupancy::Occupancy;
+use crate::state::Tracker;
+use crate::{
+    audit, control, dnd, hooks, logind, named, peek, probe, profile, remote, restart, shutdown, special, udev, usage,
+    watchdog, webhook,
+};
+use crate::mqtt;
+use crate::{
+    confirm, enforce_max_windows, get_connected_monitor_names, get_current_monitor_name, get_current_workspace,
+    get_monitors, handle_unmapped_workspace, load_mapping, offer_install, on_workspace_changed, push_focus,
+    push_workspace_focus, reassign_workspaces, removed_by_dpms, restore_monitor, retry_reassignments, save_active_submap, unix_time,
+    workspace_command, MonitorConfig,
+};
+
+/// How often the focused workspace's time is credited to its usage, so a
+/// long stay shows up before focus moves on
+const USAGE_TICK: Duration = Duration::from_secs(60);
+
+// What the daemon knows about the session beyond the persisted state
+#[derive(Default)]
+pub struct Session {
+    // Monitor with focus, from focusedmon
+    pub focused_monitor: String,
+    // Active submap, empty for the default one
+    pub submap: String,
+    // Recently focused windows (addresses without 0x), most recent last
+    pub focus_history: Vec<String>,
+    // Recently focused workspaces on any monitor, most recent last
+    pub workspace_focus: Vec<i32>,
+    // Workspace being timed for `hyprws usage`, and since when
+    pub timed: (i32, u64),
+    // Monitors removed within the last disconnect_grace, and when
+    pub pending_disconnects: HashMap<String, Instant>,
+    // Monitors whose removal came from DPMS off rather than a disconnect
+    pub powered_off: HashSet<String>,
+    // Special workspace shown per monitor
+    pub specials: HashMap<String, String>,
+}
+
+impl Session {
+    // Stop holding a monitor that came back; returns whether it was held
+    fn returned(&mut self, name: &str) -> bool {
+        let pending = self.pending_disconnects.remove(name).is_some();
+        self.powered_off.remove(name) || pending
+    }
+}
+
+// State shared by the daemon's threads. The event listener does most of the
+// writing; timers, the remote server and hooks read it concurrently. Locks
+// are held only for the duration of a read or update, never across a
+// dispatch or hyprctl call.
+pub struct Shared {
+    session: RwLock<Session>,
+    occupancy: RwLock<Occupancy>,
+    pub tracker: Tracker,
+}
+
+impl Shared {
+    fn new(session: Session, occupancy: Occupancy, tracker: Tracker) -> Self {
+        Shared {
+            session: RwLock::new(session),
+            occupancy: RwLock::new(occupancy),
+            tracker,
+        }
+    }
+
+    pub fn session(&self) -> RwLockReadGuard<'_, Session> {
+        self.session.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn session_mut(&self) -> RwLockWriteGuard<'_, Session> {
+        self.session.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn occupancy(&self) -> RwLockReadGuard<'_, Occupancy> {
+        self.occupancy.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn occupancy_mut(&self) -> RwLockWriteGuard<'_, Occupancy> {
+        self.occupancy.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Focused workspace and monitor, submap, special workspaces and profile,
+    // for webhooks and `state` requests to the remote server
+    pub fn state(&self) -> serde_json::Value {
+        let session = self.session();
+        serde_json::json!({
+            "workspace": session.timed.0,
+            "monitor": session.focused_monitor,
+            "submap": session.submap,
+            "specials": session.specials,
+            "profile": profile::get_active_profile(),
+        })
+    }
+
+    // An event with the session state, for webhooks
+    fn snapshot(&self, event: &str, data: &str) -> webhook::Payload {
+        let body = serde_json::json!({
+            "event": event,
+            "data": data,
+            "time": unix_time(),
+            "state": self.state(),
+        });
+        webhook::Payload {
+            event: event.to_string(),
+            body,
+        }
+    }
+
+    // The event and session state for hook conditions: event, data, args
+    // (data split at commas), monitor (the event's, or the focused one),
+    // workspace, submap, specials and profile
+    fn hook_context(&self, event: &str, data: &str) -> serde_json::Value {
+        let (focused_monitor, workspace, submap, specials) = {
+            let session = self.session();
+            (
+                session.focused_monitor.clone(),
+                session.timed.0,
+                session.submap.clone(),
+                session.specials.clone(),
+            )
+        };
+        let args: Vec<&str> = data.split(',').collect();
+        let monitor = match event {
+            "monitoradded" | "monitorremoved" => data,
+            "monitoraddedv2" | "monitorremovedv2" => args.get(1).copied().unwrap_or_default(),
+            "focusedmon" | "focusedmonv2" => args[0],
+            _ => &focused_monitor,
+        };
+        serde_json::json!({
+            "event": event,
+            "data": data,
+            "args": args,
+            "monitor": monitor_context(monitor),
+            "workspace": {
+                "id": workspace,
+                "group": (workspace > 0).then(|| (workspace - 1) % 10 + 1),
+            },
+            "submap": submap,
+            "specials": specials,
+            "profile": profile::get_active_profile(),
+        })
+    }
+
+    // Credit the time the timed workspace has been focused so far to its
+    // usage, on every switch, every USAGE_TICK and when the daemon stops
+    fn credit_usage(&self) {
+        let now = unix_time();
+        let (timed, since) = {
+            let mut session = self.session_mut();
+            let timed = session.timed;
+            session.timed.1 = now;
+            timed
+        };
+        // Looking up UTC offsets runs date, so it's done without the locks
+        let mut credited = HashMap::new();
+        usage::credit(&mut credited, timed, since, now, usage::utc_offset_at);
+        if credited.is_empty() {
+            return;
+        }
+        self.tracker.with(|s| {
+            for (workspace, hours) in credited {
+                let total = s.usage.entry(workspace).or_insert_with(|| vec![0; 24]);
+                total.resize(24, 0);
+                total.iter_mut().zip(hours).for_each(|(total, seconds)| *total += seconds);
+            }
+        });
+    }
+
+    // Update session fields that follow from an event
+    fn track(&self, event: &str, data: &str) {
+        match event {
+            // focusedmon>>MONNAME,WORKSPACENAME
+            "focusedmon" => {
+                if let Some((monitor, workspace)) = data.split_once(',') {
+                    let mut session = self.session_mut();
+                    session.focused_monitor = monitor.to_string();
+                    if let Ok(workspace) = workspace.parse() {
+                        push_workspace_focus(&mut session.workspace_focus, workspace);
+                    }
+                }
+            }
+            "workspace" => {
+                if let Ok(workspace) = data.parse::<i32>() {
+                    self.credit_usage();
+                    let mut session = self.session_mut();
+                    push_workspace_focus(&mut session.workspace_focus, workspace);
+                    self.tracker.with(|s| s.push_workspace(&session.focused_monitor, workspace));
+                    session.timed.0 = workspace;
+                }
+            }
+            // submap>>NAME, empty when returning to the default submap
+            "submap" => {
+                self.session_mut().submap = data.to_string();
+                if let Err(e) = save_active_submap(data) {
+                    eprintln!("Warning: couldn't record submap: {}", e);
+                }
+            }
+            "activespecial" => {
+                let mut session = self.session_mut();
+                special::apply(&mut session.specials, data);
+                special::save(&session.specials);
+            }
+            // activewindowv2>>ADDRESS (without the 0x prefix)
+            "activewindowv2" if !data.is_empty() => {
+                let mut session = self.session_mut();
+                self.tracker.with(|s| {
+                    s.focus.insert(session.focused_monitor.clone(), format!("0x{}", data));
+                });
+                push_focus(&mut session.focus_history, data);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Reassign for monitors whose disconnect_grace ran out without them coming back
+fn expire_disconnects<R: Fn()>(shared: &Shared, grace: Duration, reassign: R) {
+    loop {
+        std::thread::sleep(grace.min(Duration::from_millis(250)));
+        let expired: Vec<String> = {
+            let mut session = shared.session_mut();
+            let expired = session
+                .pending_disconnects
+                .iter()
+                .filter(|(_, since)| since.elapsed() >= grace)
+                .map(|(name, _)| name.clone())
+                .collect();
+            session.pending_disconnects.retain(|_, since| since.elapsed() < grace);
+            expired
+        };
+        if !expired.is_empty() {
+            println!("{} didn't come back, reassigning workspaces...", expired.join(", "));
+            reassign();
+        }
+    }
+}
+
+// Assign workspaces, then keep them assigned as monitors come and go
+pub fn run(config: &Config, config_path: &str, socket: String, assume_yes: bool) {
+    // SIGTERM and SIGINT go to the shutdown thread, so they're blocked before
+    // any other thread starts
+    if let Err(e) = shutdown::block() {
+        eprintln!("Warning: couldn't set up signal handling: {}", e);
+    }
+    probe::check_hooks(config);
+
+    // The workspace mapping, kept in memory for the handlers and the control
+    // socket and reloaded after every reassignment
+    let mapping = RwLock::new(Vec::new());
+    let reload_mapping = || {
+        let maps = load_mapping(config_path, config);
+        *mapping.write().unwrap_or_else(|e| e.into_inner()) = maps;
+    };
+    let current_mapping = || mapping.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+    // Successful reassignments are reported to the restart thread
+    let (restart_sender, restart_receiver) = mpsc::channel();
+    let reassigned = || {
+        reload_mapping();
+        if config.has_restarts() {
+            let _ = restart_sender.send(());
+        }
+    };
+
+    // Events for webhooks are posted from their own thread
+    let (webhook_sender, webhook_receiver) = mpsc::channel();
+
+    // State changes are published to MQTT from their own thread
+    let (mqtt_sender, mqtt_receiver) = mpsc::channel();
+
+    // Failed reassignments are queued for the retry thread
+    let (retry_sender, retry_receiver) = mpsc::channel();
+    let reassign = || {
+        if let Some(path) = reassign_workspaces(config, config_path) {
+            println!("Workspaces reassigned. Configuration updated at: {}", path);
+            reassigned();
+        } else {
+            eprintln!("Failed to reassign workspaces, retrying with backoff");
+            let _ = retry_sender.send(());
+        }
+    };
+
+    // No scripts provided - use callback to assign workspaces when monitors change
+    let callback = |context: &monitor::MonitorContext| {
+        match context.event {
+            MonitorEvent::Added => println!("Monitor {} added, reassigning workspaces...", context.name),
+            MonitorEvent::Removed => println!("Monitor {} removed, reassigning workspaces...", context.name),
+        }
+        Action::Reassign
+    };
+
+    // Initial configuration
+    let prompt = format!("This will overwrite {} and reload Hyprland. Continue?", config_path);
+    if !confirm(&prompt, assume_yes) {
+        std::process::exit(1);
+    }
+    if let Some(quiet) = config.startup_settle.filter(|ms| *ms > 0) {
+        println!("Waiting for monitors to settle...");
+        let quiet = Duration::from_millis(quiet);
+        let timeout = Duration::from_millis(config.startup_timeout());
+        if let Err(e) = monitor::settle(&socket, quiet, timeout, get_connected_monitor_names) {
+            eprintln!("Warning: couldn't wait for monitors to settle: {}", e);
+        }
+    }
+
+    println!("Initial workspace assignment...");
+    if let Some(path) = reassign_workspaces(config, config_path) {
+        println!("Initial workspace configuration created at: {}", path);
+        reload_mapping();
+        offer_install(&path);
+    } else {
+        eprintln!("Failed to create initial workspace configuration");
+        std::process::exit(1);
+    }
+
+    let session = Session {
+        focused_monitor: get_current_monitor_name(),
+        timed: (get_current_workspace(), unix_time()),
+        specials: special::query(),
+        ..Default::default()
+    };
+    special::save(&session.specials);
+    let _ = save_active_submap("");
+
+    // Window counts kept current from events, shared with the CLI
+    let occupancy = Occupancy::seed();
+    if let Err(e) = occupancy.save() {
+        eprintln!("Warning: couldn't write occupancy cache: {}", e);
+    }
+
+    // Workspace history and focus memory, restored from the last checkpoint
+    let shared = Shared::new(session, occupancy, Tracker::restore());
+    let grace = config.disconnect_grace.filter(|ms| *ms > 0).map(Duration::from_millis);
+
+    let handler = |event: &str, data: &str| {
+        let _busy = watchdog::busy(event);
+        audit::set_trigger(format!("event: {}>>{}", event, data));
+        {
+            let mut occupancy = shared.occupancy_mut();
+            if occupancy.apply(event, data) {
+                if let Err(e) = occupancy.save() {
+                    eprintln!("Warning: couldn't write occupancy cache: {}", e);
+                }
+            }
+        }
+        shared.track(event, data);
+        // Rules may have changed with the config, e.g. ws.conf edited by hand
+        if event == "configreloaded" {
+            reload_mapping();
+            named::forget_rules();
+        }
+        if webhook::wanted(&config.webhooks, event) {
+            let _ = webhook_sender.send(shared.snapshot(event, data));
+        }
+        if config.mqtt.is_some() && mqtt::EVENTS.contains(&event) {
+            let _ = mqtt_sender.send(());
+        }
+
+        // Hooks stay quiet while a listed submap (e.g. resize mode) is active
+        let suppressed = config.suppresses_hooks(&shared.session().submap);
+        let pipeline = config.hooks.get(event).filter(|_| !suppressed);
+        // What the pipeline's `when` conditions test
+        let hook_context = pipeline
+            .filter(|p| hooks::has_conditions(p))
+            .map(|_| shared.hook_context(event, data));
+
+        // A pipeline with the reassign builtin replaces the default handling
+        if !pipeline.is_some_and(|p| hooks::handles_reassign(p, hook_context.as_ref())) {
+            match event {
+                "monitoradded" | "monitorremoved" => {
+                    if let Some(context) = monitor::MonitorContext::from_event(event, data) {
+                        on_monitor_event(&shared, &context, grace, config, config_path, || {
+                            monitor::perform(callback(&context), &context, reassign)
+                        });
+                    }
+                }
+                "workspace" => on_workspace_changed(data, config),
+                // focusedmon>>MONNAME,WORKSPACENAME: focus moved to another monitor's workspace
+                "focusedmon" => {
+                    if let Some(workspace) = data.split_once(',').and_then(|(_, ws)| ws.parse().ok()) {
+                        dnd::focused(workspace, config);
+                    }
+                }
+                "createworkspace" | "movewindowv2" if config.unmapped_workspaces != UnmappedPolicy::Ignore => {
+                    let maps = current_mapping();
+                    handle_unmapped_workspace(event, data, &maps, config);
+                }
+                "openwindow" => {
+                    if config.unmapped_workspaces != UnmappedPolicy::Ignore {
+                        let maps = current_mapping();
+                        handle_unmapped_workspace(event, data, &maps, config);
+                    }
+                    if let Some(max_windows) = config.max_windows.filter(|m| *m > 0) {
+                        let maps = current_mapping();
+                        let clients = shared.occupancy().clients();
+                        enforce_max_windows(data, &clients, &maps, config, max_windows);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(pipeline) = pipeline {
+            hooks::run_pipeline(pipeline, event, data, hook_context.as_ref(), || {
+                let success = reassign_workspaces(config, config_path).is_some();
+                if success {
+                    reassigned();
+                }
+                success
+            });
+        }
+    };
+
+    // Start monitoring for changes
+    println!("Monitoring for display changes...");
+    watchdog::notify("READY=1");
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            audit::set_trigger("retry".to_string());
+            retry_reassignments(retry_receiver, config, config_path, reassigned)
+        });
+        if config.has_restarts() {
+            scope.spawn(|| restart::watch(restart_receiver, config, config_path));
+        }
+        scope.spawn(|| shared.tracker.checkpoint_loop());
+        scope.spawn(|| loop {
+            std::thread::sleep(USAGE_TICK);
+            shared.credit_usage();
+        });
+        scope.spawn(|| watchdog::watch(Duration::from_secs(config.watchdog_stall())));
+        scope.spawn(|| {
+            audit::set_trigger("peek timer".to_string());
+            peek::watch()
+        });
+        if let Some(grace) = grace {
+            let shared = &shared;
+            scope.spawn(move || {
+                audit::set_trigger("disconnect grace".to_string());
+                expire_disconnects(shared, grace, reassign)
+            });
+        }
+        if config.logind {
+            scope.spawn(|| logind::watch(|event| {
+                println!("logind: {:?}, resyncing workspaces...", event);
+                audit::set_trigger(format!("logind: {:?}", event));
+                if let logind::LoginEvent::LidClosed(closed) = event {
+                    logind::apply_lid_policy(closed, config);
+                }
+                reassign();
+            }));
+        }
+        if !config.webhooks.is_empty() {
+            scope.spawn(|| webhook::deliver(webhook_receiver, &config.webhooks));
+        }
+        if let Some(mqtt) = &config.mqtt {
+            scope.spawn(|| mqtt::publish_state(mqtt_receiver, mqtt));
+        }
+        scope.spawn(|| {
+            control::serve(|args| match args[0].as_str() {
+                "-s" | "--workspace" | "-m" | "--move" | "move" => {
+                    workspace_command(args, &current_mapping(), config)
+                }
+                other => Err(format!("The daemon doesn't run '{}'", other)),
+            })
+        });
+        if let Some(remote) = &config.remote {
+            scope.spawn(|| remote::serve(remote, &shared));
+        }
+        if config.udev {
+            scope.spawn(|| {
+                audit::set_trigger("udev".to_string());
+                udev::watch(reassign)
+            });
+        }
+
+        scope.spawn(|| shutdown::wait(|| stop(&shared, config)));
+
+        // Exit from inside the scope; the helper threads never finish on their own
+        if let Err(e) = monitor::listen_events(socket, handler) {
+            eprintln!("Error listening to Hyprland socket: {}", e);
+            stop(&shared, config);
+            std::process::exit(1);
+        }
+    });
+}
+
+// Undo what the daemon changed for the session and save its state before
+// it exits
+fn stop(shared: &Shared, config: &Config) {
+    dnd::restore(config);
+    shared.credit_usage();
+    shared.tracker.checkpoint();
+}
+
+// A monitor for hook conditions, live if Hyprland still has it (a removed
+// one only has its cached details)
+fn monitor_context(name: &str) -> serde_json::Value {
+    if let Some(m) = get_monitors().into_iter().find(|m| m.name == name) {
+        return serde_json::json!({
+            "name": m.name,
+            "desc": m.description,
+            "width": m.width,
+            "height": m.height,
+            "refresh_rate": m.refresh_rate,
+            "x": m.x,
+            "y": m.y,
+            "scale": m.scale,
+            "focused": m.focused,
+        });
+    }
+    let cached = MonitorConfig::load()
+        .ok()
+        .and_then(|config| config.monitors.into_values().find(|m| m.name == name));
+    match cached {
+        Some(m) => serde_json::json!({
+            "name": m.name,
+            "desc": m.description,
+            "width": m.width,
+            "height": m.height,
+            "refresh_rate": m.refresh_rate,
+            "x": m.x,
+            "y": m.y,
+            "scale": m.scale,
+            "focused": false,
+        }),
+        None => serde_json::json!({ "name": name }),
+    }
+}
+
+// monitoradded/monitorremoved, holding off while a removed monitor may come
+// back within disconnect_grace
+fn on_monitor_event<F: FnOnce()>(
+    shared: &Shared,
+    context: &monitor::MonitorContext,
+    grace: Option<Duration>,
+    config: &Config,
+    config_path: &str,
+    default: F,
+) {
+    match context.event {
+        // Turning a screen off for the night isn't a reason to reshuffle
+        MonitorEvent::Removed if !config.reassign_on_dpms && removed_by_dpms(&context.name) => {
+            println!("Monitor {} was powered off, keeping its workspaces", context.name);
+            shared.session_mut().powered_off.insert(context.name.clone());
+        }
+        // A brief blank (e.g. a DP link retrain) shouldn't shuffle every window
+        MonitorEvent::Removed if grace.is_some() => {
+            println!("Monitor {} removed, waiting for it to come back...", context.name);
+            shared.session_mut().pending_disconnects.insert(context.name.clone(), Instant::now());
+        }
+        MonitorEvent::Added if shared.session_mut().returned(&context.name) => {
+            println!("Monitor {} is back, restoring its workspaces", context.name);
+            let last_active = shared
+                .tracker
+                .snapshot()
+                .workspace_history
+                .get(&context.name)
+                .and_then(|h| h.last().copied());
+            restore_monitor(config_path, &context.name, last_active, config);
+        }
+        _ => default(),
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    get_connected_monitor_names, get_workspaces, instance_suffix, ipc, monitor_cache_path, parse_workspace_file,
+    MonitorConfig,
+};
+
+// What `hyprws debug monitors` shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonitorsView {
+    // The parsed monitor config, saved to the cache
+    Parsed,
+    // monitors all -j as Hyprland sends it
+    Raw,
+    // Live monitors against the cached monitors.json
+    Diff,
+}
+
+pub fn monitors(view: MonitorsView) -> Result<(), String> {
+    if view == MonitorsView::Raw {
+        let json = ipc::request("j/monitors all");
+        if json.is_empty() {
+            return Err("No answer to the monitors request".to_string());
+        }
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let mut live = MonitorConfig::new();
+    live.update_from_hyprland()
+        .map_err(|e| format!("Error updating monitor config: {}", e))?;
+
+    if view == MonitorsView::Diff {
+        let cached = MonitorConfig::load().map_err(|e| format!("Couldn't read the monitor cache: {}", e))?;
+        let changes = diff(&cached, &live);
+        if changes.is_empty() {
+            println!("monitors{}.json matches the live monitors", instance_suffix());
+        }
+        for change in changes {
+            println!("{}", change);
+        }
+        return Ok(());
+    }
+
+    let json = serde_json::to_string_pretty(&live).map_err(|e| e.to_string())?;
+    println!("Monitor config JSON:\n{}", json);
+    live.save().map_err(|e| format!("Error saving monitor config: {}", e))?;
+    println!("Monitor config saved to {}", monitor_cache_path());
+    Ok(())
+}
+
+// Monitors keyed by name; IDs change as monitors come and go
+fn by_name(config: &MonitorConfig) -> BTreeMap<&str, serde_json::Value> {
+    config
+        .monitors
+        .values()
+        .map(|m| (m.name.as_str(), serde_json::to_value(m).unwrap_or_default()))
+        .collect()
+}
+
+// One line per monitor only one side has, and per field that differs:
+// "- NAME" cached only, "+ NAME" live only, "~ NAME FIELD: CACHED -> LIVE"
+fn diff(cached: &MonitorConfig, live: &MonitorConfig) -> Vec<String> {
+    let (cached, live) = (by_name(cached), by_name(live));
+    let mut changes = Vec::new();
+    for name in cached.keys().filter(|name| !live.contains_key(*name)) {
+        changes.push(format!("- {} (cached, not connected)", name));
+    }
+    for (name, monitor) in &live {
+        let Some(old) = cached.get(name) else {
+            changes.push(format!("+ {} (connected, not cached)", name));
+            continue;
+        };
+        let (Some(old), Some(new)) = (old.as_object(), monitor.as_object()) else {
+            continue;
+        };
+        for (field, value) in new {
+            let previous = old.get(field).cloned().unwrap_or_default();
+            if previous != *value {
+                changes.push(format!("~ {} {}: {} -> {}", name, field, previous, value));
+            }
+        }
+    }
+    changes
+}
+
+// Report where monitors.json, ws.conf and Hyprland disagree; returns how
+// many inconsistencies were found
+pub fn drift(ws_path: &str) -> usize {
+    let connected = get_connected_monitor_names();
+    let mut problems = Vec::new();
+
+    match MonitorConfig::load() {
+        Ok(cached) => {
+            let cached: Vec<&str> = cached.enabled().map(|m| m.name.as_str()).collect();
+            for name in cached.iter().filter(|name| !connected.iter().any(|c| c == *name)) {
+                problems.push(format!("{} is in monitors{}.json but not connected", name, instance_suffix()));
+            }
+            for name in connected.iter().filter(|name| !cached.contains(&name.as_str())) {
+                problems.push(format!("{} is connected but not in monitors{}.json", name, instance_suffix()));
+            }
+        }
+        Err(e) => problems.push(format!("Couldn't read the monitor cache: {}", e)),
+    }
+
+    let maps = parse_workspace_file(ws_path);
+    if maps.is_empty() {
+        problems.push(format!("{} assigns no workspaces", ws_path));
+    }
+    let mut unknown: BTreeMap<&str, Vec<i32>> = BTreeMap::new();
+    for map in maps.iter().filter(|m| !connected.contains(&m.monitor)) {
+        unknown.entry(&map.monitor).or_default().push(map.workspace);
+    }
+    for (monitor, workspaces) in unknown {
+        let workspaces: Vec<String> = workspaces.iter().map(|ws| ws.to_string()).collect();
+        problems.push(format!("{} assigns {} to {}, which isn't connected", ws_path, workspaces.join(", "), monitor));
+    }
+    for name in connected.iter().filter(|name| !maps.iter().any(|m| &m.monitor == *name)) {
+        problems.push(format!("{} has no workspaces in {}", name, ws_path));
+    }
+
+    let workspaces = get_workspaces();
+    for workspace in workspaces.iter().filter(|w| w.id > 0) {
+        match maps.iter().find(|m| m.workspace == workspace.id) {
+            Some(map) if map.monitor != workspace.monitor && connected.contains(&map.monitor) => problems.push(format!(
+                "Workspace {} is on {}, but {} puts it on {}",
+                workspace.id, workspace.monitor, ws_path, map.monitor
+            )),
+            None => problems.push(format!("Workspace {} on {} isn't in {}", workspace.id, workspace.monitor, ws_path)),
+            _ => {}
+        }
+    }
+
+    if problems.is_empty() {
+        println!("No drift between monitors.json, {} and Hyprland", ws_path);
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    problems.len()
+}
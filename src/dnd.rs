@@ -0,0 +1,61 @@
+use std::sync::Mutex;
+
+use crate::config::{Config, DndBackend};
+use crate::process::output;
+
+// mako mode hiding notifications
+const MAKO_MODE: &str = "do-not-disturb";
+
+/// Whether do-not-disturb was already on when focus entered a dnd group,
+/// so leaving restores it; None while focus is elsewhere
+static ENTERED: Mutex<Option<bool>> = Mutex::new(None);
+
+fn is_on(backend: DndBackend) -> Option<bool> {
+    match backend {
+        DndBackend::Mako => output("makoctl", &["mode"]).map(|modes| modes.lines().any(|m| m.trim() == MAKO_MODE)),
+        DndBackend::Swaync => output("swaync-client", &["--get-dnd"]).map(|state| state.trim() == "true"),
+    }
+}
+
+fn set(backend: DndBackend, on: bool) {
+    match backend {
+        DndBackend::Mako => output("makoctl", &["mode", if on { "-a" } else { "-r" }, MAKO_MODE]),
+        DndBackend::Swaync => output("swaync-client", &[if on { "--dnd-on" } else { "--dnd-off" }]),
+    };
+}
+
+// Turn do-not-disturb on when the focused workspace enters a dnd group, and
+// back off on leaving unless it was on already
+pub fn focused(workspace: i32, config: &Config) {
+    let Some(backend) = config.dnd_backend.filter(|_| !config.dnd_groups.is_empty()) else {
+        return;
+    };
+    let mut entered = ENTERED.lock().unwrap_or_else(|e| e.into_inner());
+    match (config.is_dnd_group(workspace), *entered) {
+        (true, None) => {
+            let was_on = is_on(backend).unwrap_or(false);
+            if !was_on {
+                set(backend, true);
+            }
+            *entered = Some(was_on);
+        }
+        (false, Some(was_on)) => {
+            if !was_on {
+                set(backend, false);
+            }
+            *entered = None;
+        }
+        _ => {}
+    }
+}
+
+// Turn do-not-disturb back off if focus turned it on, e.g. when the daemon
+// stops while a dnd group is focused
+pub fn restore(config: &Config) {
+    let Some(backend) = config.dnd_backend else {
+        return;
+    };
+    if ENTERED.lock().unwrap_or_else(|e| e.into_inner()).take() == Some(false) {
+        set(backend, false);
+    }
+}
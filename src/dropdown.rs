@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::{dispatch_batch, get_clients, get_monitor_geometry};
+
+// How long to wait for a freshly started terminal to map its window
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Address of the dropdown window and whether it is already on its special workspace
+fn find_window(class: &str, special: &str) -> Option<(String, bool)> {
+    get_clients()
+        .into_iter()
+        .find(|c| c.class == class)
+        .map(|c| {
+            let on_special = c.workspace.name == special;
+            (c.address, on_special)
+        })
+}
+
+// Toggle a quake-style terminal on the special workspace of the same name,
+// starting it first when it isn't running
+pub fn toggle(name: &str, config: &Config) -> Result<(), String> {
+    let dropdown = config
+        .dropdowns
+        .get(name)
+        .ok_or_else(|| format!("No [dropdowns.{}] section in config", name))?;
+    if dropdown.command.is_empty() || dropdown.class.is_empty() {
+        return Err(format!("[dropdowns.{}] needs both command and class", name));
+    }
+
+    let special = format!("special:{}", name);
+    let mut window = find_window(&dropdown.class, &special);
+
+    if window.is_none() {
+        dispatch_batch(&[format!("exec [workspace {} silent] {}", special, dropdown.command)]);
+
+        let started = std::time::Instant::now();
+        while window.is_none() && started.elapsed() < STARTUP_TIMEOUT {
+            std::thread::sleep(Duration::from_millis(100));
+            window = find_window(&dropdown.class, &special);
+        }
+    }
+
+    let Some((address, on_special)) = window else {
+        return Err(format!("'{}' didn't open a window of class '{}'", dropdown.command, dropdown.class));
+    };
+
+    let mut dispatches = Vec::new();
+    if !on_special {
+        dispatches.push(format!("movetoworkspacesilent {},address:{}", special, address));
+    }
+
+    // Cover the top of whichever monitor is focused right now
+    if let Some((x, y, width, height)) = get_monitor_geometry(|m| m.focused) {
+        let window_height = (height * dropdown.height.clamp(0.1, 1.0)) as i32;
+        dispatches.push(format!("setfloating address:{}", address));
+        dispatches.push(format!("resizewindowpixel exact {} {},address:{}", width as i32, window_height, address));
+        dispatches.push(format!("movewindowpixel exact {} {},address:{}", x as i32, y as i32, address));
+    }
+
+    dispatches.push(format!("togglespecialworkspace {}", name));
+    dispatch_batch(&dispatches);
+    Ok(())
+}
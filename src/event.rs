@@ -0,0 +1,65 @@
+// Framing and parsing of the socket2 event stream. Kept free of other
+// hyprws modules so the fuzz target can include it on its own.
+
+// Longest line kept while waiting for its newline; Hyprland's events are far
+// shorter, so anything longer is garbage and gets dropped
+pub const MAX_LINE: usize = 64 * 1024;
+
+// One EVENT>>DATA line
+#[derive(Debug, PartialEq)]
+pub struct HyprEvent {
+    pub name: String,
+    pub data: String,
+}
+
+// Parse a line without its newline; None for lines that aren't events
+pub fn parse_line(line: &[u8]) -> Option<HyprEvent> {
+    let line = String::from_utf8_lossy(line);
+    let (name, data) = line.trim().split_once(">>")?;
+    if name.is_empty() {
+        return None;
+    }
+    Some(HyprEvent {
+        name: name.to_string(),
+        data: data.to_string(),
+    })
+}
+
+// Splits the byte stream into lines, holding on to a partial line until the
+// rest of it arrives
+#[derive(Default)]
+pub struct Framer {
+    buf: Vec<u8>,
+    // Set while skipping the rest of an overlong line
+    discarding: bool,
+}
+
+impl Framer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    // The next complete event, skipping malformed lines
+    pub fn next_event(&mut self) -> Option<HyprEvent> {
+        loop {
+            let Some(end) = self.buf.iter().position(|b| *b == b'\n') else {
+                if self.buf.len() > MAX_LINE {
+                    self.buf.clear();
+                    self.discarding = true;
+                }
+                return None;
+            };
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            if std::mem::take(&mut self.discarding) {
+                continue;
+            }
+            if let Some(event) = parse_line(&line[..end]) {
+                return Some(event);
+            }
+        }
+    }
+}
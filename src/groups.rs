@@ -0,0 +1,161 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+use crate::config::Config;
+
+#[derive(Serialize, Debug)]
+struct Group {
+    group: i32,
+    description: Option<String>,
+}
+
+fn groups(config: &Config) -> Vec<Group> {
+    (1..=config.workspaces_per_monitor() as i32)
+        .map(|group| Group {
+            group,
+            description: config.description_for(group).map(str::to_string),
+        })
+        .collect()
+}
+
+// "3 – browsers", or "3" for groups without a description
+fn line(group: &Group) -> String {
+    match &group.description {
+        Some(description) => format!("{} – {}", group.group, description),
+        None => group.group.to_string(),
+    }
+}
+
+pub fn list(json: bool, config: &Config) {
+    let groups = groups(config);
+    if json {
+        if let Ok(json) = serde_json::to_string_pretty(&groups) {
+            println!("{}", json);
+        }
+        return;
+    }
+    for group in &groups {
+        println!("{}", line(group));
+    }
+}
+
+// Ask the configured picker for a group; the choice is the number its line
+// starts with
+pub fn pick(config: &Config) -> Option<i32> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(config.picker())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        for group in groups(config) {
+            let _ = writeln!(stdin, "{}", line(&group));
+        }
+    }
+
+    let output = child.wait_with_output().ok()?;
+    let choice = String::from_utf8_lossy(&output.stdout);
+    choice.split_whitespace().next()?.parse().ok()
+}
+
+// Completion scripts ask `hyprws list` for groups each time, so edited
+// descriptions show up without regenerating them
+const BASH: &str = r#"_hyprws() {
+    local cur=${COMP_WORDS[COMP_CWORD]} prev=${COMP_WORDS[COMP_CWORD-1]}
+    case $prev in
+        -s|--workspace|-m|--move|move|--silent|peek|pin)
+            COMPREPLY=($(compgen -W "$(hyprws list 2>/dev/null | cut -d' ' -f1)" -- "$cur"))
+            return ;;
+    esac
+    COMPREPLY=($(compgen -W "COMMANDS" -- "$cur"))
+}
+complete -F _hyprws hyprws
+"#;
+
+const ZSH: &str = r#"#compdef hyprws
+
+_hyprws() {
+    local -a groups commands
+    commands=(COMMANDS)
+    case ${words[CURRENT-1]} in
+        -s|--workspace|-m|--move|move|--silent|peek|pin)
+            groups=(${(f)"$(hyprws list 2>/dev/null | sed 's/ – /:/')"})
+            _describe 'group' groups ;;
+        *)
+            _describe 'command' commands ;;
+    esac
+}
+
+_hyprws "$@"
+"#;
+
+const FISH: &str = r#"complete -c hyprws -f
+complete -c hyprws -n '__fish_use_subcommand' -a 'COMMANDS'
+complete -c hyprws -n '__fish_seen_subcommand_from -s --workspace -m --move move peek pin' \
+    -a '(hyprws list 2>/dev/null | string replace " – " \t)'
+"#;
+
+// Every command the CLI dispatches on. cli::run turns away anything else
+// before dispatching, and a test checks its match arms against the list.
+pub const COMMANDS: &[&str] = &[
+    "-s", "--workspace", "-m", "--move", "move", "--monitor", "daemon", "assign", "profile", "config",
+    "migrate", "dropdown", "togglespecial", "--toggle-special", "stash", "unstash", "goto", "send-workspace",
+    "peek", "rotate", "arrange", "pin", "unpin", "set-primary", "instances", "--prev", "prev", "focus-prev",
+    "history", "usage", "--generate-module", "check", "--check-config", "import-kanshi", "install", "status",
+    "overview", "list", "pick", "completions", "debug",
+];
+
+pub fn completions(shell: &str) -> Result<String, String> {
+    let script = match shell {
+        "bash" => BASH,
+        "zsh" => ZSH,
+        "fish" => FISH,
+        other => return Err(format!("Unknown shell '{}': expected bash, zsh or fish", other)),
+    };
+    Ok(script.replace("COMMANDS", &COMMANDS.join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Commands matched on args[1] in cli::run
+    fn dispatched() -> Vec<String> {
+        let source = include_str!("cli.rs");
+        let start = source.find("match args[1].as_str() {").expect("no dispatch in cli.rs");
+        let mut commands = Vec::new();
+        for line in source[start..].lines().skip(1) {
+            if line.starts_with("        _ =>") {
+                break;
+            }
+            let Some(pattern) = line.strip_prefix("        \"").and_then(|l| l.split(" =>").next()) else {
+                continue;
+            };
+            let pattern = pattern.split(" if ").next().unwrap_or_default();
+            for command in format!("\"{}", pattern).split('|') {
+                let command = command.trim().trim_matches('"').to_string();
+                if !commands.contains(&command) {
+                    commands.push(command);
+                }
+            }
+        }
+        commands
+    }
+
+    #[test]
+    fn completions_list_every_command() {
+        let dispatched = dispatched();
+        assert!(!dispatched.is_empty(), "no commands found in cli::run");
+        for command in &dispatched {
+            assert!(COMMANDS.contains(&command.as_str()), "{} missing from COMMANDS", command);
+        }
+        for command in COMMANDS {
+            assert!(dispatched.iter().any(|c| c == command), "{} isn't a command", command);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::process::Command;
+use serde_json::Value;
+
+use crate::config::{Builtin, HookStage};
+use crate::paths::expand_home;
+use crate::process;
+
+// The process for a command stage: interpreter argv with the command as its
+// last argument, or the shell with -c
+fn command_for(stage: &HookStage, command: &str) -> Command {
+    let mut process = match stage.interpreter.split_first() {
+        Some((program, args)) => {
+            let mut process = process::command(expand_home(program));
+            process.args(args).arg(expand_home(command));
+            process
+        }
+        None => {
+            let mut process = process::command(stage.shell.as_deref().unwrap_or("sh"));
+            process.arg("-c").arg(command);
+            process
+        }
+    };
+    if let Some(cwd) = &stage.cwd {
+        process.current_dir(expand_home(cwd));
+    }
+    process.envs(&stage.env);
+    process
+}
+
+// Whether a pipeline has `when` conditions, which need the event context
+pub fn has_conditions(pipeline: &[HookStage]) -> bool {
+    pipeline.iter().any(|stage| stage.condition.is_some())
+}
+
+// Whether a stage's condition holds; stages without one always run
+fn applies(stage: &HookStage, context: Option<&Value>) -> bool {
+    stage
+        .condition
+        .as_ref()
+        .is_none_or(|condition| condition.eval(context.unwrap_or(&Value::Null)))
+}
+
+// Whether a pipeline takes over the daemon's own workspace reassignment for
+// this event
+pub fn handles_reassign(pipeline: &[HookStage], context: Option<&Value>) -> bool {
+    pipeline
+        .iter()
+        .any(|stage| stage.builtin == Some(Builtin::Reassign) && applies(stage, context))
+}
+
+// Run a hook pipeline stage by stage, waiting for each to finish. Stages
+// whose condition doesn't hold are skipped; a failing stage aborts the rest
+// unless it sets continue_on_error. Returns whether every stage that ran
+// succeeded.
+pub fn run_pipeline<F>(pipeline: &[HookStage], event: &str, data: &str, context: Option<&Value>, reassign: F) -> bool
+where
+    F: Fn() -> bool,
+{
+    let mut all_succeeded = true;
+
+    for (index, stage) in pipeline.iter().enumerate().filter(|(_, stage)| applies(stage, context)) {
+        let succeeded = match (&stage.builtin, &stage.command) {
+            (Some(Builtin::Reassign), _) => reassign(),
+            (None, Some(command)) => match command_for(stage, command)
+                .env("HYPRWS_EVENT", event)
+                .env("HYPRWS_EVENT_DATA", data)
+                .status()
+            {
+                Ok(status) => status.success(),
+                Err(e) => {
+                    eprintln!("Failed to run hook '{}': {}", command, e);
+                    false
+                }
+            },
+            (None, None) => {
+                eprintln!("Hook stage {} for '{}' has neither command nor builtin", index + 1, event);
+                false
+            }
+        };
+
+        if !succeeded {
+            all_succeeded = false;
+            if !stage.continue_on_error {
+                eprintln!("Hook stage {} for '{}' failed, skipping the rest", index + 1, event);
+                break;
+            }
+        }
+    }
+
+    all_succeeded
+}
@@ -0,0 +1,53 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+use crate::{check, ipc, lock, parse_workspace_file, query_workspace_rules};
+use crate::paths::{expand_home, hyprland_conf};
+
+// Whether a hyprland.conf line sources the generated file
+fn sources(line: &str, ws_path: &str) -> bool {
+    let line = check::strip_comment(line);
+    let Some(target) = line.trim().strip_prefix("source").and_then(|rest| rest.trim_start().strip_prefix('=')) else {
+        return false;
+    };
+    expand_home(target.trim()) == ws_path
+}
+
+// Add a `source =` line for the generated file to hyprland.conf
+pub fn install(ws_path: &str) -> io::Result<()> {
+    lock::writable()?;
+    let path = hyprland_conf();
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+    if contents.lines().any(|line| sources(line, ws_path)) {
+        println!("{} already sources {}", path, ws_path);
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let separator = if contents.is_empty() || contents.ends_with('\n') { "" } else { "\n" };
+    write!(file, "{}\n# Workspace rules generated by hyprws\nsource = {}\n", separator, ws_path)?;
+    println!("Added source = {} to {}", ws_path, path);
+    ipc::request("reload");
+    Ok(())
+}
+
+// Check that Hyprland loaded the rules just written; when it didn't,
+// hyprland.conf most likely doesn't source the file. Returns whether it did.
+pub fn verify_sourced(ws_path: &str) -> bool {
+    let expected = parse_workspace_file(ws_path);
+    let Some(rules) = query_workspace_rules() else {
+        // Older Hyprland or no answer; nothing to compare against
+        return true;
+    };
+
+    let loaded = expected
+        .iter()
+        .any(|m| rules.iter().any(|r| r.workspace == m.workspace && r.monitor == m.monitor));
+    if loaded || expected.is_empty() {
+        return true;
+    }
+
+    eprintln!("Warning: Hyprland hasn't loaded the rules in {}.", ws_path);
+    eprintln!("hyprland.conf is probably missing `source = {}`; run `hyprws install` to add it.", ws_path);
+    false
+}
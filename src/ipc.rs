@@ -0,0 +1,84 @@
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::{Mutex, OnceLock};
+use serde::de::DeserializeOwned;
+
+use crate::{monitor, read_only};
+
+// Client for Hyprland's request socket, used for every query and dispatch
+// instead of spawning hyprctl. Hyprland closes a request connection after
+// replying, so a connection is opened lazily per request; the mutex
+// serializes requests from every part of the daemon through the one client.
+pub struct IpcClient {
+    socket_path: String,
+    lock: Mutex<()>,
+}
+
+impl IpcClient {
+    pub fn new(socket_path: String) -> Self {
+        IpcClient {
+            socket_path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    // Send a raw request (e.g. "j/clients" or "dispatch workspace 3")
+    pub fn request(&self, command: &str) -> io::Result<String> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut stream = UnixStream::connect(&self.socket_path)?;
+        stream.write_all(command.as_bytes())?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    // Pipeline several dispatches in a single request
+    pub fn batch(&self, dispatches: &[String]) -> io::Result<String> {
+        let commands = dispatches
+            .iter()
+            .map(|d| format!("dispatch {}", d))
+            .collect::<Vec<_>>()
+            .join(";");
+        self.request(&format!("[[BATCH]]{}", commands))
+    }
+}
+
+/// Client for the selected Hyprland instance, set up on first use (after
+/// --instance has picked the instance)
+static CLIENT: OnceLock<Option<IpcClient>> = OnceLock::new();
+
+pub fn client() -> io::Result<&'static IpcClient> {
+    CLIENT
+        .get_or_init(|| monitor::get_hyprland_request_socket().ok().map(IpcClient::new))
+        .as_ref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "Hyprland is not running"))
+}
+
+// Send a request, e.g. "reload" or "keyword monitor DP-1,disable"; the
+// response trimmed, empty when Hyprland can't be reached
+pub fn request(command: &str) -> String {
+    if read_only() && (command == "reload" || command.starts_with("keyword ")) {
+        eprintln!("Read-only mode: not sending '{}'", command);
+        return String::new();
+    }
+    match client().and_then(|c| c.request(command)) {
+        Ok(response) => response.trim().to_string(),
+        Err(e) => {
+            eprintln!("Hyprland request '{}' failed: {}", command, e);
+            String::new()
+        }
+    }
+}
+
+// JSON request ("monitors all" sends "j/monitors all"), parsed
+pub fn query<T: DeserializeOwned>(command: &str) -> Option<T> {
+    let response = request(&format!("j/{}", command));
+    if response.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&response)
+        .map_err(|e| eprintln!("Error parsing '{}' response: {}", command, e))
+        .ok()
+}
@@ -0,0 +1,295 @@
+use std::fs;
+use std::io;
+
+// Hyprland transform numbers, indexed like kanshi's transform names
+const TRANSFORMS: [&str; 8] = ["normal", "90", "180", "270", "flipped", "flipped-90", "flipped-180", "flipped-270"];
+
+// An `output` directive inside a kanshi profile
+#[derive(Default)]
+struct Output {
+    // Connector name, or a monitor description when it was quoted with spaces
+    name: String,
+    enabled: bool,
+    mode: Option<String>,
+    position: Option<(i32, i32)>,
+    scale: Option<String>,
+    transform: Option<usize>,
+}
+
+struct Profile {
+    name: String,
+    outputs: Vec<Output>,
+    exec: Vec<String>,
+}
+
+// Split into words, keeping quoted strings whole; newlines, braces and
+// semicolons are tokens of their own
+fn tokenize(contents: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '\n' | '{' | '}' | ';' => tokens.push(c.to_string()),
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next_if(|c| *c != '"') {
+                    token.push(c);
+                }
+                chars.next();
+                tokens.push(token);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{};\"#".contains(*c)) {
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
+fn parse_output(words: &[String]) -> Option<Output> {
+    let mut output = Output {
+        name: words.first()?.clone(),
+        enabled: true,
+        ..Default::default()
+    };
+    let mut rest = words[1..].iter();
+    while let Some(word) = rest.next() {
+        match word.as_str() {
+            "enable" => output.enabled = true,
+            "disable" => output.enabled = false,
+            "mode" => {
+                let mode = rest.next()?;
+                // Hyprland takes the rate without the Hz suffix
+                output.mode = Some(mode.trim_end_matches("Hz").to_string());
+            }
+            "position" => {
+                let (x, y) = rest.next()?.split_once(',')?;
+                output.position = Some((x.parse().ok()?, y.parse().ok()?));
+            }
+            "scale" => output.scale = rest.next().cloned(),
+            "transform" => {
+                let transform = rest.next()?;
+                output.transform = TRANSFORMS.iter().position(|t| t == transform);
+            }
+            _ => {}
+        }
+    }
+    Some(output)
+}
+
+// Rejoin exec words, quoting the ones the tokenizer unquoted
+fn command(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|w| if w.contains(char::is_whitespace) { format!("\"{}\"", w) } else { w.clone() })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Profiles of a kanshi config. Outputs are given on one line or as a block
+// (`output NAME { ... }`); outputs configured outside any profile lend their
+// settings to the profiles' outputs of the same name.
+fn parse(contents: &str) -> Vec<Profile> {
+    // Words of each output directive, name first, for the profile (or None
+    // outside profiles) it's in
+    let mut directives: Vec<(Option<usize>, Vec<String>)> = Vec::new();
+    let mut profiles = Vec::new();
+    let mut in_profile = false;
+    let mut block: Option<Vec<String>> = None;
+    let mut words: Vec<String> = Vec::new();
+
+    for token in tokenize(contents) {
+        match token.as_str() {
+            "{" if block.is_none() && words.first().is_some_and(|w| w == "output") => {
+                block = Some(words.drain(..).skip(1).collect());
+            }
+            "{" if !in_profile && words.first().is_some_and(|w| w == "profile") => {
+                let name = words.get(1).cloned().unwrap_or_else(|| format!("kanshi{}", profiles.len() + 1));
+                profiles.push(Profile { name, outputs: Vec::new(), exec: Vec::new() });
+                in_profile = true;
+                words.clear();
+            }
+            "\n" | ";" | "}" => {
+                let profile = Some(profiles.len()).filter(|_| in_profile).map(|n| n - 1);
+                if let Some(block) = block.as_mut() {
+                    block.append(&mut words);
+                    if token == "}" && !block.is_empty() {
+                        directives.push((profile, std::mem::take(block)));
+                    }
+                } else {
+                    match (words.first().map(String::as_str), profile) {
+                        (Some("output"), _) if words.len() > 1 => directives.push((profile, words[1..].to_vec())),
+                        (Some("exec"), Some(profile)) => profiles[profile].exec.push(command(&words[1..])),
+                        _ => {}
+                    }
+                    if token == "}" {
+                        in_profile = false;
+                    }
+                }
+                if token == "}" {
+                    block = None;
+                }
+                words.clear();
+            }
+            _ => words.push(token),
+        }
+    }
+
+    let defaults: Vec<&Vec<String>> = directives.iter().filter(|(p, _)| p.is_none()).map(|(_, w)| w).collect();
+    for (profile, output) in directives.iter().filter_map(|(p, w)| p.map(|p| (p, w))) {
+        let mut merged = vec![output[0].clone()];
+        for default in defaults.iter().filter(|d| d[0] == output[0]) {
+            merged.extend_from_slice(&default[1..]);
+        }
+        merged.extend_from_slice(&output[1..]);
+        profiles[profile].outputs.extend(parse_output(&merged));
+    }
+    profiles
+}
+
+// kanshi matches outputs by connector or description; descriptions become
+// roles so the profile resolves them to connectors at runtime
+fn is_description(name: &str) -> bool {
+    name.contains(' ')
+}
+
+fn role_name(description: &str) -> String {
+    let slug: String = description
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+// `hyprctl keyword monitor` value for an output
+fn keyword(monitor: &str, output: &Output) -> String {
+    if !output.enabled {
+        return format!("{},disable", monitor);
+    }
+    let mut keyword = format!(
+        "{},{},{},{}",
+        monitor,
+        output.mode.as_deref().unwrap_or("preferred"),
+        output.position.map(|(x, y)| format!("{}x{}", x, y)).unwrap_or_else(|| "auto".to_string()),
+        output.scale.as_deref().unwrap_or("1"),
+    );
+    if let Some(transform) = output.transform.filter(|t| *t != 0) {
+        keyword.push_str(&format!(",transform,{}", transform));
+    }
+    keyword
+}
+
+// Convert a kanshi config into [roles] and [profiles] settings
+pub fn import(path: &str) -> io::Result<String> {
+    let profiles = parse(&fs::read_to_string(path)?);
+    if profiles.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("No profiles found in {}", path)));
+    }
+
+    let mut roles = toml::Table::new();
+    let mut tables = toml::Table::new();
+    for profile in &profiles {
+        let mut outputs: Vec<&Output> = profile.outputs.iter().filter(|o| o.name != "*").collect();
+        // Workspace blocks follow the physical left-to-right order
+        outputs.sort_by_key(|o| o.position.unwrap_or((i32::MAX, i32::MAX)));
+
+        let mut monitors = Vec::new();
+        let mut keywords = Vec::new();
+        for output in outputs {
+            let monitor = if is_description(&output.name) {
+                let role = role_name(&output.name);
+                roles.insert(role.clone(), toml::Value::String(output.name.clone()));
+                role
+            } else {
+                output.name.clone()
+            };
+            keywords.push(toml::Value::String(keyword(&monitor, output)));
+            if output.enabled {
+                monitors.push(toml::Value::String(monitor));
+            }
+        }
+
+        let mut table = toml::Table::new();
+        table.insert("monitors".to_string(), toml::Value::Array(monitors));
+        table.insert("monitor_keywords".to_string(), toml::Value::Array(keywords));
+        if !profile.exec.is_empty() {
+            let exec = profile.exec.iter().cloned().map(toml::Value::String).collect();
+            table.insert("on_enter".to_string(), toml::Value::Array(exec));
+        }
+        tables.insert(profile.name.clone(), toml::Value::Table(table));
+    }
+
+    let mut settings = toml::Table::new();
+    if !roles.is_empty() {
+        settings.insert("roles".to_string(), toml::Value::Table(roles));
+    }
+    settings.insert("profiles".to_string(), toml::Value::Table(tables));
+    toml::to_string(&settings).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_quoted_names_whole() {
+        let profiles = parse(
+            "profile docked {\n\
+             \toutput \"Dell Inc. DELL U2720Q 1234\" mode 3840x2160@60Hz position 0,0 scale 1.5\n\
+             \toutput eDP-1 disable\n\
+             }\n",
+        );
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "docked");
+        let outputs = &profiles[0].outputs;
+        assert_eq!(outputs[0].name, "Dell Inc. DELL U2720Q 1234");
+        assert_eq!(outputs[0].mode.as_deref(), Some("3840x2160@60"));
+        assert_eq!(outputs[0].position, Some((0, 0)));
+        assert_eq!(outputs[0].scale.as_deref(), Some("1.5"));
+        assert!(!outputs[1].enabled);
+        assert_eq!(role_name(&outputs[0].name), "dell-inc-dell-u2720q-1234");
+    }
+
+    #[test]
+    fn reads_output_blocks() {
+        let profiles = parse(
+            "output eDP-1 {\n  scale 2\n  transform 90\n}\n\
+             profile {\n  output eDP-1 {\n    mode 2560x1600\n    position 0,0\n  }\n  output HDMI-A-1 enable\n}\n\
+             profile laptop { output eDP-1 scale 1.25 }\n",
+        );
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "kanshi1");
+        let panel = &profiles[0].outputs[0];
+        assert_eq!(panel.mode.as_deref(), Some("2560x1600"));
+        // The settings outside the profile carry over
+        assert_eq!((panel.scale.as_deref(), panel.transform), (Some("2"), Some(1)));
+        assert_eq!(keyword("eDP-1", panel), "eDP-1,2560x1600,0x0,2,transform,1");
+        assert_eq!(profiles[0].outputs[1].name, "HDMI-A-1");
+        // ... unless the profile sets them itself
+        assert_eq!(profiles[1].outputs[0].scale.as_deref(), Some("1.25"));
+    }
+
+    #[test]
+    fn collects_exec_lines() {
+        let profiles = parse(
+            "profile home {\n\
+             \toutput DP-1 enable # main screen\n\
+             \texec notify-send \"Docked at home\"\n\
+             \texec systemctl --user restart waybar; exec true\n\
+             }\n",
+        );
+        assert_eq!(
+            profiles[0].exec,
+            ["notify-send \"Docked at home\"", "systemctl --user restart waybar", "true"]
+        );
+    }
+}
@@ -0,0 +1,2042 @@
+mod arrange;
+mod audit;
+mod bundle;
+mod check;
+mod cli;
+mod condition;
+pub mod config; // import the config module
+mod context;
+mod control;
+mod daemon;
+mod debug;
+mod dnd;
+mod dropdown;
+mod event;
+mod groups;
+mod hooks;
+mod install;
+mod ipc;
+mod kanshi;
+mod lock;
+mod logind;
+mod migrate;
+mod monitor; // import the monitor module
+mod mqtt;
+mod named;
+mod night_light;
+mod nix;
+mod occupancy;
+mod outputs;
+mod paths;
+mod peek;
+mod pin;
+mod placement;
+mod probe;
+mod process;
+mod profile;
+mod remote;
+mod restart;
+mod role;
+mod shutdown;
+mod special;
+mod stash;
+mod state;
+mod target;
+mod thumbnail;
+mod udev;
+mod usage;
+mod wallpaper;
+mod watchdog;
+mod wayland;
+mod webhook;
+use std::env;
+use std::fs::{File, create_dir_all};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::Path;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use config::{Config, FocusPolicy, MappingSource, NoMappingPolicy, RepeatSwitch, UnmappedPolicy};
+use outputs::Outputs;
+
+// Library API: the workspace group logic the CLI and daemon are built on,
+// for bars and widgets
+pub use context::Context;
+pub use event::HyprEvent;
+pub use monitor::{EventListener, EventStream};
+#[cfg(feature = "async")]
+pub use monitor::stream::AsyncEventStream;
+
+// Entry point of the hyprws binary, not part of the library API
+#[doc(hidden)]
+pub use cli::run;
+
+/// Maximum number of workspaces to create (10 per monitor)
+const MAX_WORKSPACES: usize = 100;
+
+/// Maximum number of monitors to support
+const MAX_MONITORS: usize = 10;
+
+/// Current layout version of the monitors.json cache
+const MONITOR_CACHE_VERSION: u32 = 4;
+
+/// Set by --read-only or read_only = true: switch and focus, but leave
+/// ws.conf, monitors.json and Hyprland's settings alone
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Serializes reassignments triggered from different daemon threads
+static REASSIGN_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug)]
+pub struct WorkspaceMonitorMap {
+    pub workspace: i32,
+    pub monitor: String,
+    // defaultName of the rule, which `name:NAME` arguments match. Rules of
+    // named and special workspaces have workspace 0 and the workspace as
+    // written here, e.g. name:mail.
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MonitorConfig {
+    // Missing in caches written before versioning, which count as version 0
+    #[serde(default)]
+    pub schema_version: u32,
+    // Unix time of the last refresh from Hyprland
+    pub updated_at: u64,
+    pub monitors: HashMap<String, Monitor>,
+}
+
+// Define a struct that matches hyprctl monitors -j output format
+#[derive(Deserialize, Debug)]
+struct HyprlandMonitor {
+    #[serde(rename = "name")]
+    name: String,
+    #[serde(rename = "id")]
+    id: u32,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    serial: String,
+    #[serde(rename = "width")]
+    width: u32,
+    #[serde(rename = "height")]
+    height: u32,
+    #[serde(rename = "refreshRate")]
+    refresh_rate: f32,
+    x: i32,
+    y: i32,
+    scale: f32,
+    transform: u32,
+    // Only reported by newer Hyprland releases
+    #[serde(default)]
+    disabled: bool,
+    // False while the output is powered off with `dpms off`
+    #[serde(rename = "dpmsStatus", default = "powered_on")]
+    dpms_status: bool,
+    #[serde(default)]
+    focused: bool,
+    #[serde(rename = "activeWorkspace", default)]
+    active_workspace: HyprlandWorkspaceRef,
+    // Name is empty when no special workspace is shown
+    #[serde(rename = "specialWorkspace", default)]
+    special_workspace: HyprlandWorkspaceRef,
+}
+
+fn powered_on() -> bool {
+    true
+}
+
+// Whether a monitor that was just removed only went into DPMS off. Hyprland
+// still lists such an output (a disconnected one is gone); when it doesn't,
+// the removal counts as power-related if every remaining output is off too,
+// as with `dpms off` for the night.
+fn removed_by_dpms(name: &str) -> bool {
+    let monitors: Vec<HyprlandMonitor> = ipc::query("monitors all").unwrap_or_default();
+    match monitors.iter().find(|m| m.name == name) {
+        Some(monitor) => !monitor.dpms_status,
+        None => !monitors.is_empty() && monitors.iter().all(|m| !m.dpms_status),
+    }
+}
+
+// Subset of the hyprctl clients -j output needed for occupancy counts
+#[derive(Deserialize, Debug)]
+struct HyprlandClient {
+    address: String,
+    class: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    monitor: i32,
+    workspace: HyprlandWorkspaceRef,
+    #[serde(default)]
+    floating: bool,
+    // bool on older Hyprland releases, fullscreen mode number on newer ones
+    #[serde(default)]
+    fullscreen: serde_json::Value,
+    // Addresses of the window's group, empty when it isn't grouped
+    #[serde(default)]
+    grouped: Vec<String>,
+}
+
+impl HyprlandClient {
+    fn is_fullscreen(&self) -> bool {
+        match &self.fullscreen {
+            serde_json::Value::Bool(b) => *b,
+            serde_json::Value::Number(n) => n.as_u64().unwrap_or(0) > 0,
+            _ => false,
+        }
+    }
+
+    // Weight of this window in occupancy calculations
+    fn weight(&self, config: &Config) -> f32 {
+        if self.is_fullscreen() {
+            config.weights.fullscreen
+        } else if self.floating {
+            config.weights.floating
+        } else {
+            config.weights.tiled
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct HyprlandWorkspaceRef {
+    id: i32,
+    name: String,
+}
+
+// Subset of the hyprctl workspaces -j output, also the shape of activeworkspace
+#[derive(Deserialize, Debug)]
+struct HyprlandWorkspace {
+    id: i32,
+    name: String,
+    monitor: String,
+    #[serde(rename = "monitorID", default)]
+    monitor_id: i32,
+    windows: u32,
+}
+
+// One line of `hyprws overview`
+#[derive(Serialize, Debug)]
+struct OverviewEntry {
+    id: i32,
+    name: String,
+    monitor: String,
+    windows: u32,
+    thumbnail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Monitor {
+    pub name: String,
+    pub id: u32,
+    pub height: u32,
+    pub width: u32,
+    #[serde(rename = "refresh-rate")]
+    pub refresh_rate: f32,
+    // Position in the layout, in logical pixels
+    pub x: i32,
+    pub y: i32,
+    pub scale: f32,
+    // wl_output transform: 0-3 rotate by 90° steps, 4-7 also flip
+    pub transform: u32,
+    // Connected but turned off with `monitor = NAME, disable`
+    pub disabled: bool,
+    // Make, model and serial, e.g. "Dell Inc. DELL U2720Q 1234"
+    pub description: String,
+}
+
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonitorConfig {
+    // Create a new empty monitor configuration
+    pub fn new() -> Self {
+        MonitorConfig {
+            schema_version: MONITOR_CACHE_VERSION,
+            updated_at: 0,
+            monitors: HashMap::new(),
+        }
+    }
+
+    // Load the monitor configuration from the file, upgrading older layouts
+    pub fn load() -> io::Result<Self> {
+        let path = monitor_cache_path();
+        let file = File::open(&path)?;
+        let reader = BufReader::new(file);
+
+        // serde_json errors already end with the line and column
+        let mut value: serde_json::Value = serde_json::from_reader(reader)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))?;
+
+        let version = value
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+        if version > MONITOR_CACHE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("monitors.json has schema version {} which is newer than this release", version),
+            ));
+        }
+
+        let migrated = version < MONITOR_CACHE_VERSION;
+        if migrated {
+            value = migrate_monitor_cache(value, version);
+        }
+
+        let config: Self = serde_json::from_value(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))?;
+
+        if migrated {
+            if let Err(e) = config.save() {
+                eprintln!("Warning: couldn't save migrated monitor config: {}", e);
+            }
+        }
+        Ok(config)
+    }
+
+    // Save the monitor configuration to the file
+    pub fn save(&self) -> io::Result<()> {
+        create_dir_all(paths::cache_dir())?;
+        let path = monitor_cache_path();
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        lock::write(&path, &json)
+    }
+
+    // Update the monitor configuration from hyprland data
+    pub fn update_from_hyprland(&mut self) -> io::Result<()> {
+        // "all" includes disabled monitors, which are kept but marked
+        let monitors_json = ipc::request("j/monitors all");
+        if monitors_json.is_empty() {
+            return Err(io::Error::other(
+                "Failed to get monitor information from Hyprland"
+            ));
+        }
+
+        let hyprland_monitors: Vec<HyprlandMonitor> = serde_json::from_str(&monitors_json)
+            .map_err(|e| {
+                eprintln!("Error parsing monitor JSON: {}", e);
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            })?;
+
+        // Clear existing monitors
+        self.monitors.clear();
+        self.updated_at = unix_time();
+        
+        // Convert from hyprland format to our format
+        for hypr_monitor in hyprland_monitors {
+            let monitor = Monitor {
+                name: hypr_monitor.name,
+                id: hypr_monitor.id,
+                height: hypr_monitor.height,
+                width: hypr_monitor.width,
+                refresh_rate: hypr_monitor.refresh_rate,
+                x: hypr_monitor.x,
+                y: hypr_monitor.y,
+                scale: hypr_monitor.scale,
+                transform: hypr_monitor.transform,
+                disabled: hypr_monitor.disabled,
+                description: hypr_monitor.description,
+            };
+            
+            // Insert with ID as key
+            self.monitors.insert(monitor.id.to_string(), monitor);
+        }
+
+        Ok(())
+    }
+
+    // Update the monitor configuration from the Wayland output protocols
+    pub fn update_from_wayland(&mut self) -> io::Result<()> {
+        let monitors = wayland::query_outputs()?;
+        if monitors.is_empty() {
+            return Err(io::Error::other("No outputs reported by wlr-randr"));
+        }
+
+        self.monitors.clear();
+        self.updated_at = unix_time();
+        for monitor in monitors {
+            self.monitors.insert(monitor.id.to_string(), monitor);
+        }
+        Ok(())
+    }
+
+    // Whether the cache was refreshed within the last ttl seconds
+    pub fn is_fresh(&self, ttl: u64) -> bool {
+        unix_time().saturating_sub(self.updated_at) <= ttl
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &Monitor> {
+        self.monitors.values().filter(|m| !m.disabled)
+    }
+
+    // Whether the cached enabled monitors are exactly the connected ones
+    pub fn matches_connected(&self, connected: &[String]) -> bool {
+        self.enabled().count() == connected.len()
+            && self.enabled().all(|m| connected.contains(&m.name))
+    }
+
+    // Get enabled monitor names sorted by ID
+    pub fn get_sorted_monitor_names(&self) -> Vec<String> {
+        let mut monitor_ids: Vec<u32> = self.enabled().map(|m| m.id).collect();
+        monitor_ids.sort();
+        
+        monitor_ids.iter()
+            .map(|id| {
+                self.enabled()
+                    .find(|m| m.id == *id)
+                    .map(|m| m.name.clone())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+// Upgrade a monitors.json document one schema version at a time
+fn migrate_monitor_cache(mut value: serde_json::Value, from_version: u32) -> serde_json::Value {
+    for version in from_version..MONITOR_CACHE_VERSION {
+        let Some(object) = value.as_object_mut() else {
+            break;
+        };
+        match version {
+            // 0 -> 1: layout unchanged, only the version field was added
+            0 => {}
+            // 1 -> 2: refresh timestamp added; treat old caches as stale
+            1 => {
+                object.insert("updated_at".to_string(), serde_json::Value::from(0));
+            }
+            // 2 -> 3: monitors gained position, scale, transform and disabled.
+            // The defaults describe an untransformed monitor at the origin;
+            // zeroing updated_at makes the next use refresh the real values.
+            2 => {
+                if let Some(serde_json::Value::Object(monitors)) = object.get_mut("monitors") {
+                    for monitor in monitors.values_mut().filter_map(|m| m.as_object_mut()) {
+                        for (key, default) in [
+                            ("x", serde_json::Value::from(0)),
+                            ("y", serde_json::Value::from(0)),
+                            ("scale", serde_json::Value::from(1.0)),
+                            ("transform", serde_json::Value::from(0)),
+                            ("disabled", serde_json::Value::from(false)),
+                        ] {
+                            monitor.entry(key).or_insert(default);
+                        }
+                    }
+                }
+                object.insert("updated_at".to_string(), serde_json::Value::from(0));
+            }
+            // 3 -> 4: monitors gained their description
+            3 => {
+                if let Some(serde_json::Value::Object(monitors)) = object.get_mut("monitors") {
+                    for monitor in monitors.values_mut().filter_map(|m| m.as_object_mut()) {
+                        monitor.entry("description").or_insert(serde_json::Value::from(""));
+                    }
+                }
+                object.insert("updated_at".to_string(), serde_json::Value::from(0));
+            }
+            _ => {}
+        }
+        object.insert("schema_version".to_string(), serde_json::Value::from(version + 1));
+    }
+    value
+}
+
+// The monitors.json cache of the selected instance
+fn monitor_cache_path() -> String {
+    format!("{}/monitors{}.json", paths::cache_dir(), instance_suffix())
+}
+
+fn read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+// Suffix for per-instance file names, empty unless --instance was given
+fn instance_suffix() -> String {
+    context::instance().map(|sig| format!("-{}", sig)).unwrap_or_default()
+}
+
+// Directory for files the daemon shares with CLI invocations while it runs:
+// $XDG_RUNTIME_DIR/hyprws, or a per-user directory in /tmp without one
+fn runtime_dir() -> String {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.is_empty() => format!("{}/hyprws", dir),
+        _ => {
+            let uid = std::fs::metadata("/proc/self").map(|m| m.uid()).unwrap_or(0);
+            format!("/tmp/hyprws-{}", uid)
+        }
+    }
+}
+
+// Path of a runtime file; create_runtime_dir() before writing it
+fn runtime_path(file: &str) -> String {
+    format!("{}/{}", runtime_dir(), file)
+}
+
+// Create the runtime directory private to the user. An existing one must be
+// a directory of ours rather than, say, a symlink planted in /tmp.
+fn create_runtime_dir() -> io::Result<()> {
+    let dir = runtime_dir();
+    match std::fs::symlink_metadata(&dir) {
+        Ok(metadata) => {
+            let uid = std::fs::metadata("/proc/self")?.uid();
+            if !metadata.is_dir() || metadata.uid() != uid {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} isn't a directory owned by this user", dir),
+                ));
+            }
+            if metadata.mode() & 0o077 != 0 {
+                std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+            }
+            Ok(())
+        }
+        Err(_) => std::fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir),
+    }
+}
+
+// Submap the daemon last saw Hyprland enter; empty for the default one
+fn get_active_submap() -> String {
+    std::fs::read_to_string(runtime_path(&format!("submap{}", instance_suffix())))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn save_active_submap(submap: &str) -> io::Result<()> {
+    write_runtime_file(&format!("submap{}", instance_suffix()), submap)
+}
+
+fn write_runtime_file(file: &str, contents: &str) -> io::Result<()> {
+    create_runtime_dir()?;
+    std::fs::write(runtime_path(file), contents)
+}
+
+/// Most windows remembered for focus-prev
+const FOCUS_HISTORY_LENGTH: usize = 20;
+
+// Record a focus change in the daemon's window history (most recent last)
+fn push_focus(history: &mut Vec<String>, address: &str) {
+    history.retain(|a| a != address);
+    history.push(address.to_string());
+    if history.len() > FOCUS_HISTORY_LENGTH {
+        history.remove(0);
+    }
+    if let Err(e) = write_runtime_file(&format!("focus{}", instance_suffix()), &history.join("\n")) {
+        eprintln!("Warning: couldn't record focus history: {}", e);
+    }
+}
+
+/// Most workspaces remembered for --prev
+const WORKSPACE_FOCUS_LENGTH: usize = 20;
+
+// Record a workspace focused on any monitor in the daemon's history (most
+// recent last), for --prev
+fn push_workspace_focus(history: &mut Vec<i32>, workspace: i32) {
+    if history.last() == Some(&workspace) {
+        return;
+    }
+    history.retain(|ws| *ws != workspace);
+    history.push(workspace);
+    if history.len() > WORKSPACE_FOCUS_LENGTH {
+        history.remove(0);
+    }
+    let lines: Vec<String> = history.iter().map(|ws| ws.to_string()).collect();
+    if let Err(e) = write_runtime_file(&format!("workspaces{}", instance_suffix()), &lines.join("\n")) {
+        eprintln!("Warning: couldn't record workspace history: {}", e);
+    }
+}
+
+// Go back to the previously focused workspace's group and to the monitor it's
+// on, wherever focus was since. The `~` target only looks at one monitor.
+fn switch_to_previous_workspace(maps: &[WorkspaceMonitorMap], config: &Config) -> Result<(), String> {
+    let history = std::fs::read_to_string(runtime_path(&format!("workspaces{}", instance_suffix())))
+        .map_err(|_| "No workspace history; is the daemon running?".to_string())?;
+    let current = get_current_workspace();
+    let live = get_workspaces();
+    let previous = history
+        .lines()
+        .rev()
+        .filter_map(|line| line.parse::<i32>().ok())
+        .find(|ws| *ws != current && live.iter().any(|w| w.id == *ws))
+        .ok_or_else(|| "No previous workspace to go back to".to_string())?;
+
+    if current <= 0 || current % 10 != previous % 10 {
+        switch_workspace(previous, maps, config);
+    }
+    // switch_workspace leaves focus on this monitor
+    if let Some(workspace) = live.iter().find(|w| w.id == previous) {
+        if workspace.monitor != get_current_monitor_name() {
+            dispatch_batch(&[format!("focusmonitor {}", workspace.monitor)]);
+        }
+    }
+    Ok(())
+}
+
+// Focus the previously focused window, wherever it is. Hyprland's own
+// focus history stays within a workspace.
+fn focus_previous_window() -> Result<(), String> {
+    let history = std::fs::read_to_string(runtime_path(&format!("focus{}", instance_suffix())))
+        .map_err(|_| "No focus history; is the daemon running?".to_string())?;
+    let open: Vec<String> = occupancy::cached_clients()
+        .unwrap_or_else(get_clients)
+        .into_iter()
+        .map(|c| c.address)
+        .collect();
+
+    // Skip the window that has focus now
+    let previous = history
+        .lines()
+        .rev()
+        .skip(1)
+        .map(|address| format!("0x{}", address))
+        .find(|address| open.contains(address))
+        .ok_or_else(|| "No previous window to focus".to_string())?;
+    dispatch_batch(&[format!("focuswindow address:{}", previous)]);
+    Ok(())
+}
+
+// Warn when the written rules didn't take effect, and offer to fix
+// hyprland.conf when someone is at the terminal
+fn offer_install(ws_path: &str) {
+    if install::verify_sourced(ws_path)
+        || !io::stdin().is_terminal()
+        || !confirm("Add the source line to hyprland.conf now?", false)
+    {
+        return;
+    }
+    if let Err(e) = install::install(ws_path) {
+        eprintln!("Couldn't update hyprland.conf: {}", e);
+    }
+}
+
+// Focused workspace, active profile, submap and open special workspaces,
+// for bars and scripts
+fn show_status(json: bool) {
+    let active = get_active_workspace();
+    let workspace = active.as_ref().map(|w| w.id);
+    let monitor = active.map(|w| w.monitor).unwrap_or_default();
+    let profile = profile::get_active_profile().unwrap_or_default();
+    let submap = get_active_submap();
+    let specials = special::visible();
+
+    if json {
+        let status = serde_json::json!({
+            "workspace": workspace,
+            "monitor": monitor,
+            "profile": profile,
+            "submap": submap,
+            "specials": specials,
+        });
+        println!("{}", status);
+        return;
+    }
+
+    println!("workspace: {} on {}", workspace.unwrap_or_default(), monitor);
+    println!("profile: {}", if profile.is_empty() { "none" } else { &profile });
+    println!("submap: {}", if submap.is_empty() { "none" } else { &submap });
+    let mut specials: Vec<String> = specials.iter().map(|(m, s)| format!("{} on {}", s, m)).collect();
+    specials.sort();
+    println!("specials: {}", if specials.is_empty() { "none".to_string() } else { specials.join(", ") });
+}
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn get_monitors() -> Vec<HyprlandMonitor> {
+    ipc::query("monitors").unwrap_or_default()
+}
+
+fn get_connected_monitor_names() -> Vec<String> {
+    get_monitors().into_iter().map(|m| m.name).collect()
+}
+
+// Helper function to get or create monitor config. A cached config is only
+// used while it is younger than the TTL and lists the connected monitors.
+fn get_monitor_config(settings: &Config) -> MonitorConfig {
+    let cached = MonitorConfig::load().ok().filter(|config| {
+        config.is_fresh(settings.monitor_cache_ttl())
+            && config.matches_connected(&get_connected_monitor_names())
+    });
+
+    match cached {
+        Some(config) => config,
+        None => {
+            let mut config = MonitorConfig::new();
+            if let Err(e) = config.update_from_hyprland() {
+                eprintln!("Warning: couldn't update monitor config: {}", e);
+            }
+            // Try to save the new config
+            if let Err(e) = config.save() {
+                eprintln!("Warning: couldn't save monitor config: {}", e);
+            }
+            config
+        }
+    }
+}
+
+
+// Ask the user to confirm a destructive operation. Only prompts when run
+// from a terminal, so keybinds and exec-once are never blocked.
+fn confirm(prompt: &str, assume_yes: bool) -> bool {
+    if assume_yes || !io::stdin().is_terminal() {
+        return true;
+    }
+
+    print!("{} [y/N] ", prompt);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+// Send several dispatches through a single request
+fn dispatch_batch(dispatches: &[String]) {
+    if dispatches.is_empty() {
+        return;
+    }
+    audit::record(dispatches);
+
+    if let Err(e) = ipc::client().and_then(|ipc| ipc.batch(dispatches)) {
+        eprintln!("Error sending dispatches: {}", e);
+    }
+}
+
+// Dispatches from automatic features (overflow, pins, relocation). Window
+// moves into or out of a protected group are dropped here, so no feature
+// can bypass protected_groups.
+fn dispatch_automatic(dispatches: &[String], config: &Config) {
+    if config.protected_groups.is_empty() {
+        dispatch_batch(dispatches);
+        return;
+    }
+
+    let clients = occupancy::cached_clients().unwrap_or_else(get_clients);
+    let allowed: Vec<String> = dispatches
+        .iter()
+        .filter(|dispatch| {
+            // movetoworkspace[silent] N,address:ADDRESS
+            let Some((target, address)) = dispatch
+                .split_once(' ')
+                .filter(|(name, _)| name.starts_with("movetoworkspace"))
+                .and_then(|(_, args)| args.split_once(",address:"))
+            else {
+                return true;
+            };
+            let source = clients.iter().find(|c| c.address == address).map(|c| c.workspace.id);
+            let protected = target.parse().is_ok_and(|ws| config.is_protected(ws))
+                || source.is_some_and(|ws| config.is_protected(ws));
+            if protected {
+                println!("Not moving window {}: its group is protected", address);
+            }
+            !protected
+        })
+        .cloned()
+        .collect();
+    dispatch_batch(&allowed);
+}
+
+// A `workspace = N, monitor:NAME` rule with any spacing, the monitor
+// anywhere among the rules and a trailing comment
+fn parse_workspace_rule(line: &str) -> Option<WorkspaceMonitorMap> {
+    let line = check::strip_comment(line);
+    let (key, value) = line.split_once('=')?;
+    if key.trim() != "workspace" {
+        return None;
+    }
+    let mut rules = value.split(',').map(str::trim);
+    let workspace = rules.next()?;
+    let rules: Vec<&str> = rules.collect();
+    let monitor = rules.iter().find_map(|rule| rule.strip_prefix("monitor:"))?;
+    let name = rules.iter().find_map(|rule| rule.strip_prefix("defaultName:"));
+    rule_map(workspace, monitor.trim(), name.map(str::trim))
+}
+
+// The mapping entry of a rule binding `workspace` to `monitor`
+fn rule_map(workspace: &str, monitor: &str, default_name: Option<&str>) -> Option<WorkspaceMonitorMap> {
+    let (workspace, name) = match workspace.parse() {
+        Ok(workspace) => (workspace, default_name),
+        Err(_) if named::parse(workspace).is_some() => (0, Some(workspace)),
+        Err(_) => return None,
+    };
+    Some(WorkspaceMonitorMap {
+        workspace,
+        monitor: monitor.to_string(),
+        name: name.filter(|n| !n.is_empty()).map(str::to_string),
+    })
+}
+
+// Numbered workspaces bound to a monitor by the file's rules
+fn parse_workspace_file(path: &str) -> Vec<WorkspaceMonitorMap> {
+    let mut maps = workspace_file_rules(path);
+    maps.retain(|m| m.workspace > 0);
+    maps
+}
+
+fn workspace_file_rules(path: &str) -> Vec<WorkspaceMonitorMap> {
+    let mut maps = Vec::new();
+    if let Err(e) = collect_workspace_rules(path, &mut Vec::new(), &mut maps) {
+        eprintln!("Failed to open workspace file '{}': {}", path, e);
+    }
+    maps
+}
+
+// Rules of a file and, in order, of the files it sources (with
+// profile_outputs ws.conf sources the active layout's file)
+fn collect_workspace_rules(path: &str, visited: &mut Vec<String>, maps: &mut Vec<WorkspaceMonitorMap>) -> io::Result<()> {
+    let file = File::open(path)?;
+    // Compared canonically, so layouts/../ws.conf is ws.conf
+    let canonical = std::fs::canonicalize(path).map_or(path.to_string(), |p| p.to_string_lossy().into_owned());
+    if visited.contains(&canonical) {
+        return Ok(());
+    }
+    visited.push(canonical);
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let line = check::strip_comment(&line);
+        let source = line.trim().strip_prefix("source").and_then(|rest| rest.trim_start().strip_prefix('='));
+        match source {
+            Some(source) => {
+                let source = check::expand(source.trim(), Path::new(path));
+                if let Err(e) = collect_workspace_rules(&source, visited, maps) {
+                    eprintln!("Failed to open workspace file '{}': {}", source, e);
+                }
+            }
+            None => maps.extend(parse_workspace_rule(&line)),
+        }
+    }
+    Ok(())
+}
+
+// The workspace mapping, from ws.conf or, with mapping_source = "hyprland",
+// from the rules Hyprland actually loaded (which may come from several files)
+fn load_mapping(path: &str, config: &Config) -> Vec<WorkspaceMonitorMap> {
+    match config.mapping_source {
+        MappingSource::File => parse_workspace_file(path),
+        MappingSource::Hyprland => query_workspace_rules().unwrap_or_else(|| {
+            eprintln!("Warning: couldn't query workspace rules, reading {}", path);
+            parse_workspace_file(path)
+        }),
+    }
+}
+
+// Entry of `hyprctl workspacerules -j`
+#[derive(Deserialize, Debug)]
+struct HyprlandWorkspaceRule {
+    #[serde(rename = "workspaceString")]
+    workspace: String,
+    #[serde(default)]
+    monitor: String,
+    #[serde(rename = "defaultName", default)]
+    default_name: String,
+}
+
+// Numbered workspaces bound to a monitor by the loaded rules
+fn query_workspace_rules() -> Option<Vec<WorkspaceMonitorMap>> {
+    let mut maps = loaded_workspace_rules()?;
+    maps.retain(|m| m.workspace > 0);
+    Some(maps)
+}
+
+fn loaded_workspace_rules() -> Option<Vec<WorkspaceMonitorMap>> {
+    let rules: Vec<HyprlandWorkspaceRule> = ipc::query("workspacerules")?;
+    Some(
+        rules
+            .iter()
+            .filter(|r| !r.monitor.is_empty())
+            .filter_map(|r| rule_map(&r.workspace, &r.monitor, Some(&r.default_name)))
+            .collect(),
+    )
+}
+
+// Named and special workspaces bound to a monitor, by the rules Hyprland
+// loaded or else by ws.conf
+fn named_workspace_rules(path: &str) -> Vec<WorkspaceMonitorMap> {
+    let mut maps = loaded_workspace_rules().unwrap_or_else(|| workspace_file_rules(path));
+    maps.retain(|m| m.workspace == 0);
+    maps
+}
+
+// Modified to use the monitor config. `layout` names the profile the rules
+// are for, which picks their file when profile_outputs is set.
+fn assign_workspaces(path: &str, layout: Option<&str>, config: &Config) -> Option<String> {
+    // Get monitor configuration
+    let mut monitor_config = get_monitor_config(config);
+    
+    // Update with latest information
+    let mut updated = monitor_config.update_from_hyprland();
+    if let Err(e) = &updated {
+        if config.wayland_fallback {
+            eprintln!("Error updating monitor configuration: {}, trying Wayland outputs", e);
+            updated = monitor_config.update_from_wayland();
+        }
+    }
+
+    if let Err(e) = updated {
+        eprintln!("Error updating monitor configuration: {}", e);
+        // Fall back to the old method if updating fails
+        let monitors = get_connected_monitor_names();
+        if monitors.is_empty() {
+            // Hyprland isn't answering (e.g. mid hotplug); keep the current mapping
+            return None;
+        }
+        return assign_layout(path, layout, &monitors, config);
+    }
+    
+    // Save the updated configuration
+    if let Err(e) = monitor_config.save() {
+        eprintln!("Warning: couldn't save monitor config: {}", e);
+    }
+    
+    // Blocks by placement policy, stable across reconnects
+    let (blocks, outputs) = match placement::place(&monitor_config, config) {
+        Ok(placed) => placed,
+        Err(e) => {
+            eprintln!("{}", e);
+            return None;
+        }
+    };
+
+    let assigned = assign_blocks(path, layout, &blocks, &outputs, config)?;
+    placement::remember(&monitor_config, &blocks);
+    Some(assigned)
+}
+
+// File a layout's rules are generated into: ws.conf itself, or with
+// profile_outputs a per-profile file next to it (ws.NAME.conf)
+fn layout_path(path: &str, layout: Option<&str>, config: &Config) -> String {
+    if !config.profile_outputs {
+        return path.to_string();
+    }
+    let name = layout.unwrap_or("default");
+    match path.strip_suffix(".conf") {
+        Some(stem) => format!("{}.{}.conf", stem, name),
+        None => format!("{}.{}", path, name),
+    }
+}
+
+// Make ws.conf a shim sourcing the generated file of the active layout, so
+// switching profiles never rewrites another profile's rules
+fn select_layout(path: &str, output: &str) -> Option<String> {
+    if path == output {
+        return Some(path.to_string());
+    }
+    let shim = format!("# Generated by hyprws: sources the active layout\nsource = {}\n", output);
+    if std::fs::read_to_string(path).is_ok_and(|current| current == shim) {
+        return Some(path.to_string());
+    }
+    if let Err(e) = lock::write(path, shim.as_bytes()) {
+        eprintln!("Unable to write workspace include '{}': {}", path, e);
+        return None;
+    }
+    ipc::request("reload");
+    println!("Selected layout {}", output);
+    Some(path.to_string())
+}
+
+// Generate a layout's rules and make it the active one
+fn assign_layout(path: &str, layout: Option<&str>, monitors: &[String], config: &Config) -> Option<String> {
+    match outputs::apply(&in_block_order(monitors), config) {
+        Ok((blocks, outputs)) => assign_blocks(path, layout, &blocks, &outputs, config),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
+}
+
+// Same with a block index chosen for each monitor and [[outputs]] applied
+fn assign_blocks(
+    path: &str,
+    layout: Option<&str>,
+    blocks: &[(usize, String)],
+    outputs: &Outputs,
+    config: &Config,
+) -> Option<String> {
+    let output = layout_path(path, layout, config);
+    assign_workspaces_to_monitors(&output, blocks, outputs, config)?;
+    select_layout(path, &output)
+}
+
+// Reassign workspaces after a monitor change, switching to a profile whose
+// monitor list matches the connected monitors when there is one
+fn reassign_workspaces(config: &Config, path: &str) -> Option<String> {
+    let _guard = REASSIGN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let connected = get_connected_monitor_names();
+    let matched = profile::match_profile(config, &connected);
+
+    if let Some(name) = matched {
+        if profile::get_active_profile().as_deref() != Some(name) {
+            println!("Switching to profile '{}'", name);
+            return match profile::apply_profile(name, config, path) {
+                Ok(()) => Some(path.to_string()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            };
+        }
+    }
+
+    let assigned = assign_workspaces(path, matched, config);
+    if assigned.is_some() {
+        rehome_orphaned_workspaces(path, config, &connected);
+        pin::enforce(&load_mapping(path, config), config);
+    }
+    assigned
+}
+
+// The primary monitor, if one is set and connected
+fn primary_monitor(config: &Config, connected: &[String]) -> Option<String> {
+    state::load()
+        .primary
+        .or_else(|| config.primary.clone())
+        .and_then(|name| role::resolve(&name, config))
+        .filter(|name| connected.contains(name))
+}
+
+// Move numbered workspaces that no rule places on a connected monitor, e.g.
+// ones left behind by a removed monitor, to the primary monitor
+fn rehome_orphaned_workspaces(path: &str, config: &Config, connected: &[String]) {
+    let Some(primary) = primary_monitor(config, connected) else {
+        return;
+    };
+    let maps = load_mapping(path, config);
+    let workspaces: Vec<HyprlandWorkspace> =
+        get_workspaces();
+
+    let dispatches: Vec<_> = workspaces
+        .iter()
+        .filter(|w| w.id > 0 && w.monitor != primary)
+        .filter(|w| !maps.iter().any(|m| m.workspace == w.id && connected.contains(&m.monitor)))
+        .map(|w| format!("moveworkspacetomonitor {} {}", w.id, primary))
+        .collect();
+    if !dispatches.is_empty() {
+        println!("Moving {} orphaned workspace(s) to {}", dispatches.len(), primary);
+        dispatch_batch(&dispatches);
+    }
+}
+
+// Move the workspaces a returning monitor owns back onto it, finishing with
+// the one it showed last so that one is visible again
+fn restore_monitor(path: &str, monitor: &str, last_active: Option<i32>, config: &Config) {
+    let workspaces: Vec<HyprlandWorkspace> =
+        get_workspaces();
+    let mut owned: Vec<i32> = load_mapping(path, config)
+        .into_iter()
+        .filter(|m| m.monitor == monitor)
+        .map(|m| m.workspace)
+        .filter(|ws| workspaces.iter().any(|w| w.id == *ws && w.monitor != monitor))
+        .collect();
+    owned.sort_by_key(|ws| Some(*ws) == last_active);
+
+    let dispatches: Vec<_> = owned
+        .iter()
+        .map(|ws| format!("moveworkspacetomonitor {} {}", ws, monitor))
+        .collect();
+    if !dispatches.is_empty() {
+        println!("Restoring {} workspace(s) to {}", dispatches.len(), monitor);
+        dispatch_batch(&dispatches);
+    }
+}
+
+// Retry failed reassignments with exponential backoff so the mapping
+// converges once Hyprland settles after a hotplug storm
+fn retry_reassignments<F: Fn()>(receiver: mpsc::Receiver<()>, config: &Config, path: &str, reassigned: F) {
+    const INITIAL_DELAY: Duration = Duration::from_millis(500);
+    const MAX_DELAY: Duration = Duration::from_secs(30);
+
+    while receiver.recv().is_ok() {
+        let mut delay = INITIAL_DELAY;
+        loop {
+            std::thread::sleep(delay);
+            // Failures queued while waiting are covered by this attempt
+            while receiver.try_recv().is_ok() {}
+
+            if reassign_workspaces(config, path).is_some() {
+                println!("Workspaces reassigned after retry");
+                reassigned();
+                break;
+            }
+            if delay >= MAX_DELAY {
+                eprintln!("Giving up reassigning workspaces until the next monitor event");
+                break;
+            }
+            delay = (delay * 2).min(MAX_DELAY);
+        }
+    }
+}
+
+// Monitors given in order take consecutive blocks
+fn in_block_order(monitors: &[String]) -> Vec<(usize, String)> {
+    monitors.iter().cloned().enumerate().collect()
+}
+
+// Build the ws.conf rules for monitors with the block index each one owns;
+// `slots` limits monitors with fixed workspaces to part of their block
+fn build_block_rules(
+    blocks: &[(usize, String)],
+    slots: &HashMap<String, (usize, usize)>,
+    config: &Config,
+) -> Result<Vec<String>, String> {
+    // Each monitor owns a block of 10 workspace numbers so that % 10 identifies
+    // the group; only the first workspaces_per_monitor of each block are used
+    let block_size = 10;
+    let workspaces_per_monitor = config.workspaces_per_monitor();
+
+    // Leaving monitors without workspaces would break switching on them, so
+    // refuse instead of truncating
+    if blocks.len() > MAX_WORKSPACES / block_size || blocks.iter().any(|(b, _)| *b >= MAX_WORKSPACES / block_size) {
+        return Err(format!(
+            "{} monitors are enabled, but workspace groups fit at most {} ({} workspaces).\n\
+             Disable outputs you don't use, or add a profile whose monitors list names at most {} of them.",
+            blocks.len(),
+            MAX_WORKSPACES / block_size,
+            MAX_WORKSPACES,
+            MAX_WORKSPACES / block_size,
+        ));
+    }
+
+    let mut rules = Vec::new();
+    for (index, monitor) in blocks {
+        let (first, last) = slots.get(monitor).copied().unwrap_or((1, workspaces_per_monitor));
+        for slot in first..=last {
+            let workspace = index * block_size + slot;
+            rules.push(match config.names.get(&slot.to_string()) {
+                Some(name) => format!("workspace = {}, monitor:{}, defaultName:{}", workspace, monitor, name),
+                None => format!("workspace = {}, monitor:{}", workspace, monitor),
+            });
+        }
+    }
+    Ok(rules)
+}
+
+// Helper function to assign workspaces to the specified monitors
+fn assign_workspaces_to_monitors(
+    path: &str,
+    blocks: &[(usize, String)],
+    outputs: &Outputs,
+    config: &Config,
+) -> Option<String> {
+    let rules = match build_block_rules(blocks, &outputs.slots, config) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return None;
+        }
+    };
+    let mut contents: String = rules.iter().map(|rule| format!("{}\n", rule)).collect();
+    for mirror in &outputs.mirrors {
+        contents.push_str(&format!("monitor = {}\n", mirror));
+    }
+
+    // Skip the rewrite and reload when the mapping hasn't changed, e.g. when a
+    // monitor briefly renegotiates
+    if std::fs::read_to_string(path).is_ok_and(|current| current == contents) {
+        println!("Workspace mapping unchanged, skipping reload");
+        return Some(path.to_string());
+    }
+
+    match lock::write(path, contents.as_bytes()) {
+        Ok(()) => {
+            ipc::request("reload");
+            
+            println!("Created {} workspaces across {} monitors", rules.len(), blocks.len());
+            
+            // Return the path as an Option<String>
+            Some(path.to_string())
+        },
+        Err(e) => {
+            eprintln!("Unable to write workspace file '{}': {}", path, e);
+            None
+        }
+    }
+}
+
+fn get_active_workspace() -> Option<HyprlandWorkspace> {
+    ipc::query("activeworkspace")
+}
+
+fn get_workspaces() -> Vec<HyprlandWorkspace> {
+    ipc::query("workspaces").unwrap_or_default()
+}
+
+fn get_current_workspace() -> i32 {
+    get_active_workspace().map_or(0, |w| w.id)
+}
+
+fn get_monitor_count() -> i32 {
+    get_monitors().len().max(1) as i32
+}
+
+fn get_current_monitor() -> i32 {
+    get_active_workspace().map_or(0, |w| w.monitor_id)
+}
+
+fn get_current_monitor_name() -> String {
+    get_active_workspace().map(|w| w.monitor).unwrap_or_default()
+}
+
+// Resolve a --to-monitor argument (current, next, primary, a role or a
+// monitor name) to a monitor name
+fn resolve_monitor(target: &str, maps: &[WorkspaceMonitorMap], config: &Config) -> Option<String> {
+    match target {
+        "current" => Some(get_current_monitor_name()),
+        "primary" => primary_monitor(config, &get_connected_monitor_names()),
+        "next" => {
+            // Monitors in the order their workspace blocks were assigned
+            let mut names: Vec<&str> = Vec::new();
+            for map in maps {
+                if !names.contains(&map.monitor.as_str()) {
+                    names.push(&map.monitor);
+                }
+            }
+
+            let current = get_current_monitor_name();
+            let index = names.iter().position(|n| *n == current)?;
+            Some(names[(index + 1) % names.len()].to_string())
+        }
+        name => role::resolve(name, config),
+    }
+}
+
+fn get_clients() -> Vec<HyprlandClient> {
+    ipc::query("clients").unwrap_or_default()
+}
+
+// The focused window; Hyprland answers {} when there is none
+fn get_active_window() -> Option<HyprlandClient> {
+    serde_json::from_str(&ipc::request("j/activewindow")).ok()
+}
+
+// Windows on a workspace that take part in occupancy, skipping excluded classes
+fn counted_windows<'a>(
+    clients: &'a [HyprlandClient],
+    workspace: i32,
+    config: &'a Config,
+) -> impl Iterator<Item = &'a HyprlandClient> {
+    clients
+        .iter()
+        .filter(move |c| c.workspace.id == workspace && !config.is_excluded(&c.class))
+}
+
+fn count_windows(clients: &[HyprlandClient], workspace: i32, config: &Config) -> usize {
+    counted_windows(clients, workspace, config).count()
+}
+
+// Weighted occupancy of a workspace (e.g. fullscreen windows count more)
+fn workspace_occupancy(clients: &[HyprlandClient], workspace: i32, config: &Config) -> f32 {
+    counted_windows(clients, workspace, config)
+        .map(|c| c.weight(config))
+        .sum()
+}
+
+// Find the workspace with the lowest weighted occupancy
+fn least_populated_workspace(clients: &[HyprlandClient], targets: &[i32], config: &Config) -> Option<i32> {
+    let mut least_populated = None;
+
+    for ws in targets {
+        let occupancy = workspace_occupancy(clients, *ws, config);
+        match least_populated {
+            Some((_, min_occupancy)) if occupancy >= min_occupancy => {}
+            _ => least_populated = Some((*ws, occupancy)),
+        }
+    }
+
+    least_populated.map(|(ws, _)| ws)
+}
+
+// Move a newly opened window to another workspace of its group when its
+// workspace already holds more than max_windows windows
+fn enforce_max_windows(
+    event_data: &str,
+    clients: &[HyprlandClient],
+    maps: &[WorkspaceMonitorMap],
+    config: &Config,
+    max_windows: usize,
+) {
+    // openwindow>>ADDRESS,WORKSPACENAME,CLASS,TITLE
+    let mut parts = event_data.splitn(4, ',');
+    let (Some(address), Some(workspace_name), Some(class)) = (parts.next(), parts.next(), parts.next()) else {
+        return;
+    };
+
+    // Only numbered workspaces belong to a group
+    let Ok(workspace) = workspace_name.parse::<i32>() else {
+        return;
+    };
+
+    if config.is_excluded(class) {
+        return;
+    }
+
+    if count_windows(clients, workspace, config) <= max_windows {
+        return;
+    }
+
+    let targets: Vec<_> = maps
+        .iter()
+        .filter(|m| m.workspace % 10 == workspace % 10 && m.workspace != workspace)
+        .map(|m| m.workspace)
+        .collect();
+
+    match least_populated_workspace(clients, &targets, config) {
+        Some(target) if count_windows(clients, target, config) < max_windows => {
+            dispatch_automatic(&[format!("movetoworkspacesilent {},address:0x{}", target, address)], config);
+        }
+        _ => eprintln!("Workspace {} is full and no other workspace in its group has room", workspace),
+    }
+}
+
+// Workspaces of the same group as a workspace, one per monitor
+fn group_targets(workspace: i32, maps: &[WorkspaceMonitorMap]) -> Vec<i32> {
+    maps.iter()
+        .filter(|m| m.workspace % 10 == workspace % 10)
+        .map(|m| m.workspace)
+        .collect()
+}
+
+fn move_silent_workspace(workspace: i32, maps: &[WorkspaceMonitorMap], config: &Config) {
+    if workspace <= 0 {
+        eprintln!("Invalid workspace number");
+        return;
+    }
+
+    let targets = group_targets(workspace, maps);
+    
+    if targets.is_empty() {
+        eprintln!("No matching workspaces found");
+        return;
+    }
+
+    // The clients and active window queries are independent, so run them
+    // concurrently instead of paying for two socket round trips in a row.
+    // A running daemon's occupancy cache saves the clients query entirely.
+    let (clients, group) = std::thread::scope(|scope| {
+        let group = scope.spawn(get_active_group);
+        let clients = occupancy::cached_clients().unwrap_or_else(get_clients);
+        (clients, group.join().unwrap_or_default())
+    });
+
+    let least_populated = match least_populated_workspace(&clients, &targets, config) {
+        Some(ws) => ws,
+        None => return,
+    };
+
+    move_window_group(least_populated, &group);
+}
+
+// Silently move every window on the focused workspace that matches to the
+// least-populated workspace of a group, in one batch
+fn move_matching<F>(workspace: i32, maps: &[WorkspaceMonitorMap], config: &Config, matches: F)
+where
+    F: Fn(&HyprlandClient) -> bool,
+{
+    let targets = group_targets(workspace, maps);
+    if targets.is_empty() {
+        eprintln!("No matching workspaces found");
+        return;
+    }
+
+    let current = get_current_workspace();
+    let clients = get_clients();
+    let Some(target) = least_populated_workspace(&clients, &targets, config) else {
+        return;
+    };
+
+    let dispatches: Vec<_> = clients
+        .iter()
+        .filter(|c| c.workspace.id == current && matches(c))
+        .map(|c| format!("movetoworkspacesilent {},address:{}", target, c.address))
+        .collect();
+    if dispatches.is_empty() {
+        eprintln!("No matching windows on the current workspace");
+        return;
+    }
+    dispatch_batch(&dispatches);
+}
+
+// Silently move to the group's workspace on a specific monitor, bypassing
+// the least-populated heuristic
+fn move_silent_to_monitor(workspace: i32, maps: &[WorkspaceMonitorMap], target: &str, config: &Config) {
+    if workspace <= 0 {
+        eprintln!("Invalid workspace number");
+        return;
+    }
+
+    let Some(monitor) = resolve_monitor(target, maps, config) else {
+        eprintln!("Couldn't resolve monitor '{}'", target);
+        return;
+    };
+
+    match maps
+        .iter()
+        .find(|m| m.workspace % 10 == workspace % 10 && m.monitor == monitor)
+    {
+        Some(map) => move_active_window(map.workspace),
+        None => eprintln!("No workspace of group {} found on monitor {}", workspace, monitor),
+    }
+}
+
+// Addresses of every window in the active window's group (empty if ungrouped)
+fn get_active_group() -> Vec<String> {
+    get_active_window().map(|w| w.grouped).unwrap_or_default()
+}
+
+// Silently move the active window, keeping a tabbed group together
+fn move_active_window(workspace: i32) {
+    move_window_group(workspace, &get_active_group());
+}
+
+// Silently move the active window, or every member of its group
+fn move_window_group(workspace: i32, group: &[String]) {
+    if group.len() < 2 {
+        dispatch_batch(&[format!("movetoworkspacesilent {}", workspace)]);
+        return;
+    }
+
+    // Lock groups so members aren't pulled into other groups mid-move.
+    // Hyprland doesn't report whether groups are locked already, so the lock
+    // is toggled and toggled back rather than set and cleared; groups the
+    // user locked are locked again afterwards.
+    let mut dispatches = vec!["lockgroups toggle".to_string()];
+    for address in group {
+        dispatches.push(format!("movetoworkspacesilent {},address:{}", workspace, address));
+    }
+    dispatches.push("lockgroups toggle".to_string());
+    dispatch_batch(&dispatches);
+}
+
+fn get_active_window_address() -> String {
+    get_active_window().map(|w| w.address).unwrap_or_default()
+}
+
+// Apply the post_move_focus policy once a window has been moved
+fn apply_focus_policy(policy: FocusPolicy, address: &str) {
+    match policy {
+        FocusPolicy::Follow => dispatch_batch(&[format!("focuswindow address:{}", address)]),
+        FocusPolicy::Previous => dispatch_batch(&["focuscurrentorlast".to_string()]),
+        FocusPolicy::Default | FocusPolicy::Stay => {}
+    }
+}
+
+fn move_workspace(workspace: i32, maps: &[WorkspaceMonitorMap], config: &Config) {
+    let address = get_active_window_address();
+    move_silent_workspace(workspace, maps, config);
+
+    // Without a policy, focus follows the whole group to the moved window
+    if config.post_move_focus != FocusPolicy::Default {
+        apply_focus_policy(config.post_move_focus, &address);
+        return;
+    }
+
+    let dispatches: Vec<_> = maps
+        .iter()
+        .filter(|m| m.workspace % 10 == workspace % 10)
+        .map(|m| format!("workspace {}", m.workspace))
+        .collect();
+    dispatch_batch(&dispatches);
+}
+
+fn switch_workspace(workspace: i32, maps: &[WorkspaceMonitorMap], config: &Config) {
+    if workspace <= 0 {
+        eprintln!("Invalid workspace number");
+        return;
+    }
+
+    let current_workspace = get_current_workspace();
+    let monitor_count = get_monitor_count();
+
+    let targets = group_targets(workspace, maps);
+    
+    if targets.is_empty() {
+        match config.no_mapping {
+            NoMappingPolicy::Fail => eprintln!("No matching workspaces found"),
+            NoMappingPolicy::Dispatch => dispatch_batch(&[format!("workspace {}", workspace)]),
+            NoMappingPolicy::Compute | NoMappingPolicy::Reassign => {
+                let maps = fallback_mapping(config);
+                if group_targets(workspace, &maps).is_empty() {
+                    eprintln!("No matching workspaces found");
+                } else {
+                    switch_workspace(workspace, &maps, config);
+                }
+            }
+        }
+        return;
+    }
+
+    if current_workspace > 0 && current_workspace % 10 == workspace % 10 {
+        if config.repeat_switch == RepeatSwitch::Focus {
+            return;
+        }
+        let next_monitor = (get_current_monitor() + 1) % monitor_count;
+        dispatch_batch(&[format!("focusmonitor {}", next_monitor)]);
+        if config.warp_cursor {
+            warp_cursor_to_monitor(next_monitor);
+        }
+    } else {
+        let mut dispatches = Vec::new();
+        if config.hide_specials_on_switch {
+            dispatches = special::hide_dispatches();
+        }
+        let hid_specials = !dispatches.is_empty();
+        dispatches.extend(
+            recent_group_workspaces(workspace, maps)
+                .iter()
+                .map(|ws| format!("workspace {}", ws)),
+        );
+        dispatch_batch(&dispatches);
+        if hid_specials {
+            special::save(&special::query());
+        }
+    }
+
+    if config.osd {
+        show_osd(config);
+    }
+}
+
+// Mapping to retry a switch with when ws.conf has none for the group (it's
+// missing or stale): computed from the connected monitors, or rewritten
+fn fallback_mapping(config: &Config) -> Vec<WorkspaceMonitorMap> {
+    let path = config.ws_conf_path(&instance_suffix());
+    if config.no_mapping == NoMappingPolicy::Reassign {
+        println!("No mapping for the workspace, reassigning...");
+        return match reassign_workspaces(config, &path) {
+            Some(path) => load_mapping(&path, config),
+            None => Vec::new(),
+        };
+    }
+
+    let Ok((blocks, outputs)) = placement::place(&get_monitor_config(config), config) else {
+        return Vec::new();
+    };
+    build_block_rules(&blocks, &outputs.slots, config)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|rule| parse_workspace_rule(rule))
+        .collect()
+}
+
+// One workspace of the group per monitor. A monitor can end up holding
+// several (after send-workspace); the one used most recently there,
+// per the daemon's history, wins over the monitor's own block. The focused
+// monitor comes last so focus stays on it.
+fn recent_group_workspaces(workspace: i32, maps: &[WorkspaceMonitorMap]) -> Vec<i32> {
+    let live: Vec<HyprlandWorkspace> =
+        get_workspaces();
+    let history = state::load().workspace_history;
+    let focused = get_current_monitor_name();
+
+    let mut picks: Vec<(bool, i32)> = Vec::new();
+    for map in maps.iter().filter(|m| m.workspace % 10 == workspace % 10) {
+        let recent = history.get(&map.monitor).and_then(|used| {
+            used.iter()
+                .rev()
+                .find(|ws| {
+                    **ws % 10 == workspace % 10
+                        && live.iter().any(|w| w.id == **ws && w.monitor == map.monitor)
+                })
+                .copied()
+        });
+        picks.push((map.monitor == focused, recent.unwrap_or(map.workspace)));
+    }
+
+    // Stable, so other monitors keep their order
+    picks.sort_by_key(|(is_focused, _)| *is_focused);
+    picks.into_iter().map(|(_, ws)| ws).collect()
+}
+
+// Shift each monitor's active workspace to the next monitor in block order
+// (the last one's wraps around to the first), or the previous with reverse
+fn rotate_workspaces(maps: &[WorkspaceMonitorMap], reverse: bool) -> Result<(), String> {
+    let mut order: Vec<&str> = Vec::new();
+    for map in maps {
+        if !order.contains(&map.monitor.as_str()) {
+            order.push(&map.monitor);
+        }
+    }
+
+    let active: Vec<(String, i32)> = get_monitors()
+        .into_iter()
+        .map(|m| (m.name, m.active_workspace.id))
+        .collect();
+    order.retain(|name| active.iter().any(|(n, _)| n == name));
+    if order.len() < 2 {
+        return Err("Rotating needs at least two assigned monitors".to_string());
+    }
+
+    let count = order.len();
+    let dispatches: Vec<_> = order
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let (_, workspace) = active.iter().find(|(n, _)| n == name)?;
+            let next = if reverse { (index + count - 1) % count } else { (index + 1) % count };
+            Some(format!("moveworkspacetomonitor {} {}", workspace, order[next]))
+        })
+        .collect();
+    dispatch_batch(&dispatches);
+    Ok(())
+}
+
+// Apply the unmapped_workspaces policy to a createworkspace, openwindow or
+// movewindowv2 event that involves a numbered workspace the mapping lacks
+fn handle_unmapped_workspace(event: &str, data: &str, maps: &[WorkspaceMonitorMap], config: &Config) {
+    // createworkspace>>NAME, openwindow>>ADDRESS,NAME,CLASS,TITLE,
+    // movewindowv2>>ADDRESS,ID,NAME
+    let (address, name) = match event {
+        "createworkspace" => (None, data),
+        "openwindow" => match data.split(',').collect::<Vec<_>>()[..] {
+            [address, name, ..] => (Some(address), name),
+            _ => return,
+        },
+        "movewindowv2" => match data.split(',').collect::<Vec<_>>()[..] {
+            [address, _, name, ..] => (Some(address), name),
+            _ => return,
+        },
+        _ => return,
+    };
+    let Ok(workspace) = name.parse::<i32>() else {
+        return;
+    };
+    if workspace <= 0 || maps.is_empty() || maps.iter().any(|m| m.workspace == workspace) {
+        return;
+    }
+
+    match (config.unmapped_workspaces, address) {
+        (UnmappedPolicy::Adopt, None) => {
+            // The monitor whose mapped workspaces share the block, if any
+            let block = (workspace - 1) / 10;
+            let Some(owner) = maps.iter().find(|m| m.workspace > 0 && (m.workspace - 1) / 10 == block) else {
+                return;
+            };
+            let monitor = &owner.monitor;
+            println!("Adopting workspace {} into {}'s block", workspace, monitor);
+            dispatch_batch(&[format!("moveworkspacetomonitor {} {}", workspace, monitor)]);
+        }
+        (UnmappedPolicy::Relocate, _) => {
+            let focused = get_current_monitor_name();
+            let Some(target) = maps
+                .iter()
+                .filter(|m| m.workspace % 10 == workspace % 10)
+                .min_by_key(|m| m.monitor != focused)
+            else {
+                return;
+            };
+            match address {
+                Some(address) => dispatch_automatic(&[format!(
+                    "movetoworkspacesilent {},address:0x{}",
+                    target.workspace, address
+                )], config),
+                None if get_current_workspace() == workspace => {
+                    println!("Relocating workspace {} to {}", workspace, target.workspace);
+                    dispatch_batch(&[format!("workspace {}", target.workspace)]);
+                }
+                None => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+// Active workspaces of the monitors that don't have focus
+fn get_visible_elsewhere() -> Vec<i32> {
+    get_monitors()
+        .iter()
+        .filter(|m| !m.focused)
+        .map(|m| m.active_workspace.id)
+        .collect()
+}
+
+// i3-style switch: when the group's workspace is shown on another monitor,
+// bring its windows to the focused monitor, otherwise switch as usual. The
+// windows of the two workspaces are swapped rather than the workspaces
+// themselves, so each monitor keeps its own block.
+fn pull_workspace(workspace: i32, maps: &[WorkspaceMonitorMap], config: &Config) {
+    if workspace <= 0 {
+        eprintln!("Invalid workspace number");
+        return;
+    }
+
+    let visible = get_visible_elsewhere();
+    let shown = maps
+        .iter()
+        .find(|m| m.workspace % 10 == workspace % 10 && visible.contains(&m.workspace));
+    let current_monitor = get_current_monitor_name();
+    let own = maps
+        .iter()
+        .find(|m| m.workspace % 10 == workspace % 10 && m.monitor == current_monitor);
+    let (Some(shown), Some(own)) = (shown, own) else {
+        switch_workspace(workspace, maps, config);
+        return;
+    };
+
+    let mut dispatches: Vec<String> = get_clients()
+        .iter()
+        .filter_map(|c| match c.workspace.id {
+            id if id == shown.workspace => Some(format!("movetoworkspacesilent {},address:{}", own.workspace, c.address)),
+            id if id == own.workspace => Some(format!("movetoworkspacesilent {},address:{}", shown.workspace, c.address)),
+            _ => None,
+        })
+        .collect();
+    dispatches.push(format!("workspace {}", own.workspace));
+    dispatch_batch(&dispatches);
+    if config.osd {
+        show_osd(config);
+    }
+}
+
+// Send the current workspace to another monitor. If that monitor is already
+// showing a workspace of the same group, swap the two when asked instead of
+// stacking them, so each monitor keeps one workspace per group.
+fn send_workspace(target: &str, maps: &[WorkspaceMonitorMap], swap: bool, config: &Config) -> Result<(), String> {
+    let monitor = resolve_monitor(target, maps, config)
+        .ok_or_else(|| format!("Couldn't resolve monitor '{}'", target))?;
+    let current_monitor = get_current_monitor_name();
+    if monitor == current_monitor {
+        return Ok(());
+    }
+
+    let current = get_current_workspace();
+    let shown = get_monitors()
+        .iter()
+        .find(|m| m.name == monitor)
+        .map(|m| m.active_workspace.id)
+        .ok_or_else(|| format!("Monitor {} isn't connected", monitor))?;
+
+    if swap && shown > 0 && current > 0 && shown % 10 == current % 10 {
+        dispatch_batch(&[format!("swapactiveworkspaces {} {}", current_monitor, monitor)]);
+    } else {
+        dispatch_batch(&[format!("moveworkspacetomonitor {} {}", current, monitor)]);
+    }
+    Ok(())
+}
+
+// Logical geometry (x, y, width, height) of the first monitor matching
+fn get_monitor_geometry<F: Fn(&HyprlandMonitor) -> bool>(matches: F) -> Option<(f32, f32, f32, f32)> {
+    let monitors = get_monitors();
+    let monitor = monitors.iter().find(|m| matches(m))?;
+
+    // Positions are in layout coordinates, sizes are in physical pixels
+    let scale = if monitor.scale > 0.0 { monitor.scale } else { 1.0 };
+    Some((
+        monitor.x as f32,
+        monitor.y as f32,
+        monitor.width as f32 / scale,
+        monitor.height as f32 / scale,
+    ))
+}
+
+// Move the cursor to the center of a monitor
+fn warp_cursor_to_monitor(monitor_id: i32) {
+    let Some((x, y, width, height)) = get_monitor_geometry(|m| m.id as i32 == monitor_id) else {
+        eprintln!("Couldn't get geometry of monitor {}", monitor_id);
+        return;
+    };
+
+    let center_x = x + width / 2.0;
+    let center_y = y + height / 2.0;
+    dispatch_batch(&[format!("movecursor {} {}", center_x as i32, center_y as i32)]);
+}
+
+// Briefly show the focused workspace and monitor via Hyprland's notify
+fn show_osd(config: &Config) {
+    let Some(active) = get_active_workspace() else {
+        return;
+    };
+
+    // -1 = no icon
+    ipc::request(&format!(
+        "notify -1 {} rgb(88c0d0) Workspace {} on {}",
+        config.osd_duration(), active.name, active.monitor
+    ));
+}
+
+// Switch to the group of the first workspace holding a window whose title or
+// class matches the pattern, then focus that window (and so its monitor)
+fn goto_window(pattern: &str, maps: &[WorkspaceMonitorMap]) -> Result<(), String> {
+    let regex = regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))?;
+
+    let mut clients = get_clients();
+    clients.sort_by_key(|c| c.workspace.id);
+    let client = clients
+        .iter()
+        .filter(|c| c.workspace.id > 0)
+        .find(|c| regex.is_match(&c.title) || regex.is_match(&c.class))
+        .ok_or_else(|| format!("No window matches '{}'", pattern))?;
+
+    let workspace = client.workspace.id;
+    let mut dispatches: Vec<_> = maps
+        .iter()
+        .filter(|m| m.workspace % 10 == workspace % 10 && m.workspace != workspace)
+        .map(|m| format!("workspace {}", m.workspace))
+        .collect();
+    dispatches.push(format!("focuswindow address:{}", client.address));
+    dispatch_batch(&dispatches);
+    Ok(())
+}
+
+// Daemon handler for workspace>>NAME events
+fn on_workspace_changed(name: &str, config: &Config) {
+    if config.thumbnails {
+        thumbnail::capture_active_workspace();
+    }
+
+    if let Some(layout) = name.parse::<i32>().ok().and_then(|ws| config.layout_for(ws)) {
+        ipc::request(&format!("switchxkblayout {} {}", config.layout_device(), layout));
+    }
+
+    if let Some(accent) = name.parse::<i32>().ok().and_then(|ws| config.accent_for(ws)) {
+        ipc::request(&format!("keyword general:col.active_border {}", accent));
+    }
+
+    if let Ok(workspace) = name.parse() {
+        dnd::focused(workspace, config);
+    }
+}
+
+// List open workspaces with their monitor, window count and thumbnail
+fn show_overview(json: bool) {
+    let Some(mut workspaces): Option<Vec<HyprlandWorkspace>> = ipc::query("workspaces") else {
+        return;
+    };
+    workspaces.sort_by_key(|w| w.id);
+
+    let entries: Vec<OverviewEntry> = workspaces
+        .into_iter()
+        .map(|w| OverviewEntry {
+            thumbnail: thumbnail::thumbnail_path(w.id),
+            id: w.id,
+            name: w.name,
+            monitor: w.monitor,
+            windows: w.windows,
+        })
+        .collect();
+
+    if json {
+        if let Ok(json) = serde_json::to_string_pretty(&entries) {
+            println!("{}", json);
+        }
+        return;
+    }
+
+    for entry in &entries {
+        println!("{:>4}  {:<12} {:<10} {} windows", entry.id, entry.name, entry.monitor, entry.windows);
+    }
+}
+
+// Switch to a workspace group like `hyprws -s N`, through the daemon when one
+// is running
+fn switch_group(group: i32) -> Result<(), String> {
+    let args = ["-s".to_string(), group.to_string()];
+    control::forward(&args).unwrap_or_else(|| {
+        let config = config::get_config();
+        let maps = load_mapping(&config.ws_conf_path(&instance_suffix()), &config);
+        workspace_command(&args, &maps, &config)
+    })
+}
+
+// -s and -m with their arguments (args[0] is the option), run by the CLI or
+// by the daemon for a forwarded keybind
+fn workspace_command(args: &[String], maps: &[WorkspaceMonitorMap], config: &Config) -> Result<(), String> {
+    let arg = |i: usize| args.get(i).map(String::as_str).ok_or_else(|| format!("{} needs more arguments", args[0]));
+
+    if args[0] == "-s" || args[0] == "--workspace" {
+        if let Some(named) = named::parse(arg(1)?) {
+            if args.len() > 2 {
+                return Err(format!("{} takes no options with a named workspace", args[0]));
+            }
+            named::switch(&named, maps, config);
+            return Ok(());
+        }
+        let target = target::resolve(arg(1)?, maps, config)?;
+        if args.get(2).is_some_and(|a| a == "--pull") {
+            pull_workspace(target.workspace, maps, config);
+        } else {
+            switch_workspace(target.workspace, maps, config);
+        }
+        return Ok(());
+    }
+
+    match arg(1)? {
+        option @ ("--class" | "--all-floating") => {
+            let (matcher, target) = if option == "--class" {
+                let pattern = regex::Regex::new(&format!("^(?:{})$", arg(2)?))
+                    .map_err(|e| format!("Invalid class pattern: {}", e))?;
+                (Some(pattern), arg(3)?)
+            } else {
+                (None, arg(2)?)
+            };
+            let target = target::resolve(target, maps, config)?;
+            move_matching(target.workspace, maps, config, |c| match &matcher {
+                Some(pattern) => pattern.is_match(&c.class),
+                None => c.floating,
+            });
+        }
+        "-s" | "--silent" => {
+            let address = get_active_window_address();
+            if let Some(named) = named::parse(arg(2)?) {
+                if args.len() > 3 {
+                    return Err(format!("{} {} takes no options with a named workspace", args[0], args[1]));
+                }
+                named::move_window(&named, true, maps, config);
+                apply_focus_policy(config.post_move_focus, &address);
+                return Ok(());
+            }
+            let target = target::resolve(arg(2)?, maps, config)?;
+            if args.get(3).is_some_and(|a| a == "--to-monitor") {
+                move_silent_to_monitor(target.workspace, maps, arg(4)?, config);
+            } else if let Some(monitor) = &target.monitor {
+                move_silent_to_monitor(target.workspace, maps, monitor, config);
+            } else {
+                move_silent_workspace(target.workspace, maps, config);
+            }
+            apply_focus_policy(config.post_move_focus, &address);
+        }
+        workspace => match named::parse(workspace) {
+            Some(named) => named::move_window(&named, false, maps, config),
+            None => {
+                let target = target::resolve(workspace, maps, config)?;
+                move_workspace(target.workspace, maps, config);
+            }
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+    use std::fs;
+
+    fn monitor_names(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("DP-{}", i)).collect()
+    }
+
+    fn config_with(workspaces_per_monitor: usize) -> Config {
+        let mut config = Config::default();
+        config.workspaces_per_monitor = Some(workspaces_per_monitor);
+        config
+    }
+
+    fn assigned(monitors: &[String], config: &Config) -> Vec<WorkspaceMonitorMap> {
+        build_block_rules(&in_block_order(monitors), &HashMap::new(), config)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|rule| parse_workspace_rule(rule))
+            .collect()
+    }
+
+    #[test]
+    fn parses_named_and_special_workspace_rules() {
+        let rule = |line| parse_workspace_rule(line).map(|m| (m.workspace, m.monitor, m.name));
+        assert_eq!(
+            rule("workspace = 3, monitor:DP-1, defaultName:web"),
+            Some((3, "DP-1".to_string(), Some("web".to_string())))
+        );
+        assert_eq!(
+            rule("workspace = name:mail, monitor:HDMI-A-1"),
+            Some((0, "HDMI-A-1".to_string(), Some("name:mail".to_string())))
+        );
+        assert_eq!(
+            rule("workspace = special:scratch, monitor:DP-2, defaultName:x"),
+            Some((0, "DP-2".to_string(), Some("special:scratch".to_string())))
+        );
+        assert_eq!(rule("workspace = name:mail, gapsout:0"), None);
+        assert_eq!(rule("workspace = r[1-4], monitor:DP-1"), None);
+    }
+
+    #[test]
+    fn workspace_files_merge_sourced_rules() {
+        let dir = std::env::temp_dir().join(format!("hyprws-rules-{}", std::process::id()));
+        fs::create_dir_all(dir.join("layouts")).unwrap();
+        let ws_conf = dir.join("ws.conf");
+        fs::write(&ws_conf, "workspace = 1, monitor:DP-1\nsource = layouts/a.conf\nworkspace = 3, monitor:DP-3\n").unwrap();
+        // Sourcing back must not recurse forever
+        fs::write(dir.join("layouts/a.conf"), "workspace = 2, monitor:DP-2\nsource = ../ws.conf\n").unwrap();
+
+        let maps = parse_workspace_file(&ws_conf.to_string_lossy());
+        let _ = fs::remove_dir_all(&dir);
+        let workspaces: Vec<(i32, &str)> = maps.iter().map(|m| (m.workspace, m.monitor.as_str())).collect();
+        assert_eq!(workspaces, [(1, "DP-1"), (2, "DP-2"), (3, "DP-3")]);
+    }
+
+    proptest! {
+        #[test]
+        fn every_workspace_maps_to_one_monitor(count in 0usize..16, per_monitor in 0usize..14) {
+            let monitors = monitor_names(count);
+            let config = config_with(per_monitor);
+            let rules = build_block_rules(&in_block_order(&monitors), &HashMap::new(), &config).unwrap_or_default();
+            let maps = assigned(&monitors, &config);
+
+            prop_assert_eq!(maps.len(), rules.len());
+            let unique: HashSet<i32> = maps.iter().map(|m| m.workspace).collect();
+            prop_assert_eq!(unique.len(), maps.len());
+            for map in &maps {
+                let index = (map.workspace as usize - 1) / 10;
+                prop_assert_eq!(&map.monitor, &monitors[index]);
+            }
+        }
+
+        #[test]
+        fn counts_stay_within_limits(count in 0usize..32, per_monitor in 0usize..14) {
+            let monitors = monitor_names(count);
+            let config = config_with(per_monitor);
+            let maps = assigned(&monitors, &config);
+
+            // Too many monitors is an error rather than a partial mapping
+            let rules = build_block_rules(&in_block_order(&monitors), &HashMap::new(), &config);
+            prop_assert_eq!(rules.is_err(), count > MAX_MONITORS);
+            prop_assert!(maps.len() <= MAX_WORKSPACES);
+            prop_assert!(maps.iter().all(|m| m.workspace >= 1 && m.workspace as usize <= MAX_WORKSPACES));
+            let used: HashSet<&str> = maps.iter().map(|m| m.monitor.as_str()).collect();
+            prop_assert!(used.len() <= MAX_MONITORS);
+            if count <= MAX_MONITORS {
+                prop_assert_eq!(maps.len(), count * config.workspaces_per_monitor());
+            }
+        }
+
+        #[test]
+        fn switch_targets_share_the_group(count in 1usize..11, per_monitor in 1usize..11, workspace in 1i32..=100) {
+            let monitors = monitor_names(count);
+            let maps = assigned(&monitors, &config_with(per_monitor));
+            let targets = group_targets(workspace, &maps);
+
+            prop_assert!(targets.iter().all(|ws| ws % 10 == workspace % 10));
+            // At most one workspace of the group per monitor, and one on
+            // every monitor when the group is in use
+            let owners: HashSet<&str> = maps
+                .iter()
+                .filter(|m| targets.contains(&m.workspace))
+                .map(|m| m.monitor.as_str())
+                .collect();
+            prop_assert_eq!(owners.len(), targets.len());
+            let group = (workspace - 1) % 10 + 1;
+            if group as usize <= per_monitor {
+                prop_assert_eq!(targets.len(), count);
+            } else {
+                prop_assert!(targets.is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use crate::read_only;
+
+// How long a writer waits for another hyprws process to finish
+const ATTEMPTS: u32 = 50;
+const RETRY_DELAY: Duration = Duration::from_millis(20);
+
+// Advisory lock on PATH.lock, held until dropped. Files are replaced by
+// rename, so readers never see a partial write and don't need the lock;
+// it only serializes writers (and read-modify-write updates).
+pub struct FileLock {
+    _file: File,
+}
+
+pub fn exclusive(path: &str) -> io::Result<FileLock> {
+    let lock_path = format!("{}.lock", path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+
+    for _ in 0..ATTEMPTS {
+        match file.try_lock() {
+            Ok(()) => return Ok(FileLock { _file: file }),
+            Err(fs::TryLockError::WouldBlock) => thread::sleep(RETRY_DELAY),
+            Err(fs::TryLockError::Error(e)) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::WouldBlock,
+        format!("timed out waiting for {}", lock_path),
+    ))
+}
+
+// Generated files (ws.conf, monitors.json) and user config are left alone
+// in read-only mode; state and runtime files aren't covered
+pub fn writable() -> io::Result<()> {
+    if read_only() {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "read-only mode"));
+    }
+    Ok(())
+}
+
+// Replace a file's contents under its lock
+pub fn write(path: &str, contents: &[u8]) -> io::Result<()> {
+    writable()?;
+    let _lock = exclusive(path)?;
+    write_locked(path, contents)
+}
+
+// Replace a file's contents when the caller already holds its lock
+pub fn write_locked(path: &str, contents: &[u8]) -> io::Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    fs::rename(tmp_path, path)
+}
@@ -0,0 +1,180 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::{check, get_connected_monitor_names, ipc, paths, HyprlandMonitor};
+
+// systemd-logind signals the daemon reacts to
+#[derive(Debug, PartialEq)]
+pub enum LoginEvent {
+    LidClosed(bool),
+    Resumed,
+}
+
+// Parse a line of `gdbus monitor` output
+fn parse_line(line: &str) -> Option<LoginEvent> {
+    // /org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (false,)
+    if line.contains("Manager.PrepareForSleep") {
+        return line.contains("(false,)").then_some(LoginEvent::Resumed);
+    }
+    // ... PropertiesChanged ('org.freedesktop.login1.Manager', {'LidClosed': <true>}, @as [])
+    if line.contains("PropertiesChanged") && line.contains("'LidClosed': <true>") {
+        return Some(LoginEvent::LidClosed(true));
+    }
+    if line.contains("PropertiesChanged") && line.contains("'LidClosed': <false>") {
+        return Some(LoginEvent::LidClosed(false));
+    }
+    None
+}
+
+// Watch logind on the system bus and pass lid and resume events to the handler
+pub fn watch<F: Fn(LoginEvent)>(handler: F) {
+    let child = Command::new("gdbus")
+        .args(["monitor", "--system", "--dest", "org.freedesktop.login1", "--object-path", "/org/freedesktop/login1"])
+        .stdout(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Couldn't start gdbus monitor: {}", e);
+            return;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if let Some(event) = parse_line(&line) {
+            handler(event);
+        }
+    }
+
+    let _ = child.wait();
+}
+
+// The `monitor =` value Hyprland would apply to a monitor, given its
+// connector name and description: the last rule naming it (directly or by
+// desc:) in a config file and the files it sources, else the last catch-all
+// `monitor = ,...` rule with the name filled in
+fn configured_monitor(path: &str, name: &str, description: &str) -> Option<String> {
+    let (mut named, mut catch_all) = (None, None);
+    collect_monitor_rules(path, name, description, &mut Vec::new(), &mut named, &mut catch_all);
+    named.or_else(|| catch_all.map(|rest| format!("{}{}", name, rest)))
+}
+
+fn collect_monitor_rules(
+    path: &str,
+    name: &str,
+    description: &str,
+    visited: &mut Vec<String>,
+    named: &mut Option<String>,
+    catch_all: &mut Option<String>,
+) {
+    // Compared canonically, so layouts/../hyprland.conf is hyprland.conf
+    let canonical = fs::canonicalize(path).map_or(path.to_string(), |p| p.to_string_lossy().into_owned());
+    if visited.contains(&canonical) {
+        return;
+    }
+    visited.push(canonical);
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in contents.lines() {
+        let line = check::strip_comment(line);
+        let line = line.trim();
+        let Some((key, rest)) = line.split_once('=') else {
+            continue;
+        };
+        let rest = rest.trim();
+        match key.trim() {
+            "source" => {
+                let source = check::expand(rest, Path::new(path));
+                collect_monitor_rules(&source, name, description, visited, named, catch_all);
+            }
+            "monitor" => {
+                let target = rest.split(',').next().unwrap_or_default().trim();
+                let matches = match target.strip_prefix("desc:") {
+                    Some(desc) => !desc.trim().is_empty() && description.starts_with(desc.trim()),
+                    None => target == name,
+                };
+                if matches {
+                    *named = Some(rest.to_string());
+                } else if target.is_empty() {
+                    *catch_all = rest.find(',').map(|i| rest[i..].to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Apply the clamshell policy: turn the internal panel off while the lid is
+// closed and another monitor is connected, and back on when it opens
+pub fn apply_lid_policy(closed: bool, config: &Config) {
+    let Some(internal) = config.lid.internal.as_deref() else {
+        return;
+    };
+
+    if closed {
+        let external = get_connected_monitor_names().iter().any(|m| m != internal);
+        if config.lid.clamshell && external {
+            ipc::request(&format!("keyword monitor {},disable", internal));
+        }
+    } else {
+        // Bring the panel back the way hyprland.conf sets it up. Hyprland
+        // still lists it while it's disabled, description included.
+        let monitors: Vec<HyprlandMonitor> = ipc::query("monitors all").unwrap_or_default();
+        let description = monitors
+            .into_iter()
+            .find(|m| m.name == internal)
+            .map(|m| m.description)
+            .unwrap_or_default();
+        match configured_monitor(&paths::hyprland_conf(), internal, &description) {
+            Some(value) => ipc::request(&format!("keyword monitor {}", value)),
+            None => ipc::request("reload"),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule_for(contents: &str, name: &str, description: &str) -> Option<String> {
+        let dir = std::env::temp_dir().join(format!("hyprws-logind-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hyprland.conf");
+        fs::write(&path, contents).unwrap();
+        let rule = configured_monitor(&path.to_string_lossy(), name, description);
+        let _ = fs::remove_dir_all(&dir);
+        rule
+    }
+
+    #[test]
+    fn finds_the_panel_rule() {
+        let conf = "monitor = ,preferred,auto,1\n\
+                    monitor = eDP-1,1920x1080@60,0x0,1.25\n\
+                    monitor = DP-1,preferred,auto,1\n";
+        assert_eq!(rule_for(conf, "eDP-1", "BOE 0x0BCA").as_deref(), Some("eDP-1,1920x1080@60,0x0,1.25"));
+    }
+
+    #[test]
+    fn matches_panels_by_description() {
+        let conf = "monitor = desc:BOE 0x0BCA,2256x1504@60,0x0,1.5\nmonitor = ,preferred,auto,1\n";
+        assert_eq!(
+            rule_for(conf, "eDP-2", "BOE 0x0BCA (eDP-2)").as_deref(),
+            Some("desc:BOE 0x0BCA,2256x1504@60,0x0,1.5")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_catch_all_rule() {
+        let conf = "monitor = DP-1,preferred,auto,1\nmonitor = , preferred, auto, 1\n";
+        assert_eq!(rule_for(conf, "eDP-3", "BOE").as_deref(), Some("eDP-3, preferred, auto, 1"));
+        assert_eq!(rule_for("monitor = DP-1,preferred,auto,1\n", "eDP-4", "BOE"), None);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::Path;
+
+use crate::{config, lock, paths};
+
+// A workspace rule read from a hand-made ws.conf
+struct WorkspaceRule {
+    workspace: usize,
+    monitor: Option<String>,
+    name: Option<String>,
+}
+
+fn parse_rules(contents: &str) -> Vec<WorkspaceRule> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("workspace")?.trim_start().strip_prefix('=')?;
+            let mut parts = rest.split(',');
+            let workspace = parts.next()?.trim().parse().ok()?;
+
+            let mut rule = WorkspaceRule { workspace, monitor: None, name: None };
+            for part in parts {
+                match part.trim().split_once(':') {
+                    Some(("monitor", monitor)) => rule.monitor = Some(monitor.trim().to_string()),
+                    Some(("defaultName", name)) => rule.name = Some(name.trim().to_string()),
+                    _ => {}
+                }
+            }
+            Some(rule)
+        })
+        .collect()
+}
+
+// Build config.toml settings equivalent to the rules
+fn synthesize(rules: &[WorkspaceRule]) -> toml::Table {
+    // Monitors ordered by their lowest workspace, and how many workspaces each has
+    let mut monitors: Vec<(&str, usize)> = Vec::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for rule in rules {
+        let Some(monitor) = rule.monitor.as_deref() else {
+            continue;
+        };
+        *counts.entry(monitor).or_default() += 1;
+        match monitors.iter_mut().find(|(m, _)| *m == monitor) {
+            Some((_, first)) => *first = (*first).min(rule.workspace),
+            None => monitors.push((monitor, rule.workspace)),
+        }
+    }
+    monitors.sort_by_key(|(_, first)| *first);
+
+    let mut names = toml::Table::new();
+    for rule in rules {
+        if let Some(name) = &rule.name {
+            let group = (rule.workspace.saturating_sub(1)) % 10 + 1;
+            names.insert(group.to_string(), toml::Value::String(name.clone()));
+        }
+    }
+
+    let mut table = toml::Table::new();
+    let per_monitor = counts.values().copied().max().unwrap_or(10).min(10);
+    table.insert("workspaces_per_monitor".to_string(), toml::Value::Integer(per_monitor as i64));
+    if !names.is_empty() {
+        table.insert("names".to_string(), toml::Value::Table(names));
+    }
+    if !monitors.is_empty() {
+        let order = monitors.iter().map(|(m, _)| toml::Value::String(m.to_string())).collect();
+        let mut profile = toml::Table::new();
+        profile.insert("monitors".to_string(), toml::Value::Array(order));
+        let mut profiles = toml::Table::new();
+        profiles.insert("migrated".to_string(), toml::Value::Table(profile));
+        table.insert("profiles".to_string(), toml::Value::Table(profiles));
+    }
+    table
+}
+
+// Suggest hyprws replacements for workspace keybinds in hyprland.conf
+fn suggest_keybinds(contents: &str) -> Vec<String> {
+    let mut suggestions = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        let Some((bind, rest)) = line.split_once('=') else {
+            continue;
+        };
+        if !bind.trim().starts_with("bind") {
+            continue;
+        }
+
+        let parts: Vec<&str> = rest.split(',').map(|p| p.trim()).collect();
+        if parts.len() < 4 || parts[3].parse::<i32>().is_err() {
+            continue;
+        }
+        let command = match parts[2] {
+            "workspace" => "hyprws -s",
+            "movetoworkspace" => "hyprws -m",
+            "movetoworkspacesilent" => "hyprws -m -s",
+            _ => continue,
+        };
+        suggestions.push(format!(
+            "{}\n  -> {} = {}, {}, exec, {} {}",
+            line, bind.trim(), parts[0], parts[1], command, parts[3]
+        ));
+    }
+    suggestions
+}
+
+// Create config.toml from an existing ws.conf and report keybinds to convert
+pub fn migrate<F>(ws_path: &str, confirm: F) -> io::Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    lock::writable()?;
+    let rules = parse_rules(&fs::read_to_string(ws_path)?);
+    if rules.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No workspace rules found in {}", ws_path),
+        ));
+    }
+
+    let config_path = paths::config_override().unwrap_or_else(|| format!("{}/config.toml", paths::config_dir()));
+    if let Some(existing) = config::config_file_path() {
+        if !confirm(&format!("{} already exists. Replace it with {}?", existing, config_path)) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Migration cancelled"));
+        }
+        fs::remove_file(existing)?;
+    }
+
+    let contents = toml::to_string(&synthesize(&rules)).map_err(io::Error::other)?;
+    if let Some(dir) = Path::new(&config_path).parent() {
+        create_dir_all(dir)?;
+    }
+    fs::write(&config_path, contents)?;
+    println!("Wrote {} from {} workspace rules", config_path, rules.len());
+
+    let hyprland_conf = paths::hyprland_conf();
+    if let Ok(contents) = fs::read_to_string(&hyprland_conf) {
+        let suggestions = suggest_keybinds(&contents);
+        if !suggestions.is_empty() {
+            println!("\nKeybinds in {} that can use hyprws instead:", hyprland_conf);
+            for suggestion in suggestions {
+                println!("{}", suggestion);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,121 +1,407 @@
 use std::env; // read env variables
 use std::fs::File;
-use std::io::BufRead; // read unix socket
-use std::io::BufReader; // read unix socket
+use std::io::Read; // read unix socket
 use std::os::unix::fs::PermissionsExt; // check file permissions
 use std::os::unix::net::UnixStream;
-use std::process::Command; // execute system command
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
 
-// listen Hyprland socket with option to pass a callback function
-pub fn listen<F>(
-    socket_addr: String,
-    script_attached: &str,
-    script_detached: Option<&str>,
-    callback: Option<F>,
-) -> std::io::Result<()> 
+use crate::event::{Framer, HyprEvent};
+use crate::{context, process, unix_time, Monitor, MonitorConfig};
+
+// How often to try reconnecting after the event socket drops
+const RECONNECT_ATTEMPTS: u32 = 10;
+
+// A monitor being connected or disconnected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonitorEvent {
+    Added,
+    Removed,
+}
+
+// What a monitor callback gets to decide on
+#[derive(Debug)]
+pub struct MonitorContext {
+    pub event: MonitorEvent,
+    // Monitor name as sent with the event, e.g. "DP-1"
+    pub name: String,
+    // Last known details from the monitors.json cache, if it has them
+    pub monitor: Option<Monitor>,
+    // Unix time the event was received
+    pub timestamp: u64,
+}
+
+impl MonitorContext {
+    // Context for a monitoradded/monitorremoved event, None for other events
+    pub fn from_event(event: &str, data: &str) -> Option<Self> {
+        let event = match event {
+            "monitoradded" => MonitorEvent::Added,
+            "monitorremoved" => MonitorEvent::Removed,
+            _ => return None,
+        };
+        let monitor = MonitorConfig::load()
+            .ok()
+            .and_then(|mut config| {
+                let id = config.monitors.iter().find(|(_, m)| m.name == data)?.0.clone();
+                config.monitors.remove(&id)
+            });
+        Some(MonitorContext {
+            event,
+            name: data.to_string(),
+            monitor,
+            timestamp: unix_time(),
+        })
+    }
+}
+
+// What to do about a monitor event
+#[derive(Debug)]
+pub enum Action {
+    // Regenerate the workspace mapping
+    Reassign,
+    // Run a script with the monitor name as argument
+    RunScript(String),
+    Ignore,
+}
+
+// Carry out a callback's decision
+pub fn perform<R: Fn()>(action: Action, context: &MonitorContext, reassign: R) {
+    match action {
+        Action::Reassign => reassign(),
+        Action::RunScript(script) => run_script(&script, context),
+        Action::Ignore => {}
+    }
+}
+
+// listen Hyprland socket and let the callback decide what each monitor
+// event leads to
+pub fn listen<F, R>(socket_addr: String, callback: F, reassign: R) -> std::io::Result<()>
 where
-    F: Fn(&str, bool) + 'static,
+    F: Fn(&MonitorContext) -> Action,
+    R: Fn(),
 {
-    let stream = match UnixStream::connect(socket_addr) {
-        Ok(stream) => stream,
+    listen_events(socket_addr, |event, data| {
+        if let Some(context) = MonitorContext::from_event(event, data) {
+            perform(callback(&context), &context, &reassign);
+        }
+    })
+}
+
+// Run a user script with the monitor as argument, after checking that the
+// user has permission to execute it. The rest of the context goes into
+// HYPRWS_MONITOR_EVENT (added/removed), HYPRWS_EVENT_TIME and, when the
+// monitor is in the cache, HYPRWS_MONITOR_SIZE (WIDTHxHEIGHT).
+fn run_script(script: &str, context: &MonitorContext) {
+    let metadata = match File::open(script).and_then(|f| f.metadata()) {
+        Ok(metadata) => metadata,
+        Err(_e) => {
+            eprintln!("Error: '{script}' file not found.");
+            return;
+        }
+    };
+    if metadata.permissions().mode() & 0o100 == 0 {
+        eprintln!("Error: '{script}' file is not executable.");
+        return;
+    }
+    let mut command = process::command(script);
+    command
+        .arg(&context.name)
+        .env("HYPRWS_MONITOR_EVENT", match context.event {
+            MonitorEvent::Added => "added",
+            MonitorEvent::Removed => "removed",
+        })
+        .env("HYPRWS_EVENT_TIME", context.timestamp.to_string());
+    if let Some(monitor) = &context.monitor {
+        command.env("HYPRWS_MONITOR_SIZE", format!("{}x{}", monitor.width, monitor.height));
+    }
+    if let Err(e) = process::spawn(&mut command) {
+        eprintln!("Error: failed to execute '{script}': {e}");
+    }
+}
+
+fn connect(socket_addr: &str) -> std::io::Result<UnixStream> {
+    UnixStream::connect(socket_addr)
+}
+
+// socket2 events as an iterator, for callers that want to drive their own
+// loop. A closed or failed socket yields one error and then ends the
+// iteration; reconnecting is up to the caller.
+pub struct EventStream {
+    stream: UnixStream,
+    framer: Framer,
+    done: bool,
+}
+
+impl EventStream {
+    pub fn connect(socket_addr: &str) -> std::io::Result<Self> {
+        Ok(EventStream {
+            stream: connect(socket_addr)?,
+            framer: Framer::new(),
+            done: false,
+        })
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = std::io::Result<HyprEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(event) = self.framer.next_event() {
+                return Some(Ok(event));
+            }
+            if self.done {
+                return None;
+            }
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.done = true;
+                    return Some(Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "Hyprland socket closed",
+                    )));
+                }
+                Ok(n) => self.framer.push(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+// Reconnect with a growing delay; Hyprland may be restarting
+fn reconnect(socket_addr: &str) -> std::io::Result<EventStream> {
+    let mut last_error = None;
+    for attempt in 1..=RECONNECT_ATTEMPTS {
+        std::thread::sleep(Duration::from_secs(attempt as u64));
+        match EventStream::connect(socket_addr) {
+            Ok(events) => {
+                println!("Reconnected to Hyprland socket");
+                return Ok(events);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| std::io::Error::other("Couldn't reconnect")))
+}
+
+// listen Hyprland socket and pass every event to the handler as (event, data).
+// Recoverable errors never end the loop: a panicking handler is logged and
+// skipped, and a dropped socket is reconnected. Only giving up on
+// reconnecting returns an error.
+pub fn listen_events<F>(socket_addr: String, mut handler: F) -> std::io::Result<()>
+where
+    F: FnMut(&str, &str),
+{
+    let mut events = match EventStream::connect(&socket_addr) {
+        Ok(events) => events,
         Err(e) => {
             println!("Couldn't connect: {e:?}");
             return Err(e);
         }
     };
-    
-    // Skip args check when a callback is provided
-    if callback.is_none() {
-        let args: Vec<String> = env::args().collect();
-        if args.len() < 2 {
-            println!("Usage: provide a script to execute.");
-            std::process::exit(1);
+
+    loop {
+        match events.next() {
+            Some(Ok(event)) => {
+                let (name, data) = (event.name.as_str(), event.data.as_str());
+                let result = panic::catch_unwind(AssertUnwindSafe(|| handler(name, data)));
+                if result.is_err() {
+                    eprintln!("Error: handling '{name}>>{data}' panicked, continuing");
+                }
+            }
+            Some(Err(e)) => {
+                eprintln!("Error reading Hyprland socket: {e}, reconnecting...");
+                // A line cut off by the disconnect is dropped with the old stream
+                events = reconnect(&socket_addr)?;
+            }
+            None => events = reconnect(&socket_addr)?,
         }
     }
-    
-    let mut reader = BufReader::new(stream);
-    loop {
-        // read message from socket
-        let mut buf: Vec<u8> = vec![];
-        reader.read_until(b'\n', &mut buf).unwrap();
-        let data = String::from_utf8_lossy(&buf);
-        let data_parts: Vec<&str> = data.trim().split(">>").collect();
-        
-        if data_parts.len() < 2 {
-            continue;
+}
+
+// socket2 of the selected Hyprland instance, for library users: iterate the
+// events or hand them to a callback with reconnects handled
+pub struct EventListener {
+    socket_addr: String,
+}
+
+impl EventListener {
+    pub fn new() -> Result<Self, String> {
+        Ok(EventListener {
+            socket_addr: get_hyprland_socket()?,
+        })
+    }
+
+    pub fn events(&self) -> std::io::Result<EventStream> {
+        EventStream::connect(&self.socket_addr)
+    }
+
+    pub fn listen<F>(self, handler: F) -> std::io::Result<()>
+    where
+        F: FnMut(&str, &str),
+    {
+        listen_events(self.socket_addr, handler)
+    }
+}
+
+// The same stream for async callers, on tokio
+#[cfg(feature = "async")]
+pub mod stream {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use futures_core::Stream;
+    use tokio::io::{AsyncRead, ReadBuf};
+    use tokio::net::UnixStream;
+
+    use crate::event::{Framer, HyprEvent};
+
+    pub struct AsyncEventStream {
+        stream: UnixStream,
+        framer: Framer,
+        done: bool,
+    }
+
+    impl AsyncEventStream {
+        pub async fn connect(socket_addr: &str) -> io::Result<Self> {
+            Ok(AsyncEventStream {
+                stream: UnixStream::connect(socket_addr).await?,
+                framer: Framer::new(),
+                done: false,
+            })
         }
-        
-        if data_parts[0] == "monitoradded" {
-            if let Some(ref func) = callback {
-                // Call the function with monitor id and is_added=true
-                func(data_parts[1], true);
-            } else {
-                // Execute script as before
-                // check user has permission to execute script
-                let metadata = {
-                    let this = File::open(script_attached);
-                    match this {
-                        Ok(t) => t,
-                        Err(_e) => {
-                            eprintln!("Error: '{script_attached}' file not found.");
-                            continue;
-                        }
-                    }
+    }
+
+    impl Stream for AsyncEventStream {
+        type Item = io::Result<HyprEvent>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let this = &mut *self;
+            let mut buf = [0u8; 4096];
+            loop {
+                if let Some(event) = this.framer.next_event() {
+                    return Poll::Ready(Some(Ok(event)));
                 }
-                .metadata()
-                .unwrap();
-                let permissions = metadata.permissions();
-                if !permissions.mode() & 0o100 != 0 {
-                    eprintln!("Error: '{script_attached}' file is not executable.");
-                    continue;
+                if this.done {
+                    return Poll::Ready(None);
                 }
-                Command::new(script_attached)
-                    .args([data_parts[1]])
-                    .spawn()
-                    .expect("Failed to execute command");
-            }
-        } else if data_parts[0] == "monitorremoved" {
-            if let Some(ref func) = callback {
-                // Call the function with monitor id and is_added=false
-                func(data_parts[1], false);
-            } else if let Some(script_detached) = script_detached {
-                let metadata = {
-                    let this = File::open(script_detached);
-                    match this {
-                        Ok(t) => t,
-                        Err(_e) => {
-                            eprintln!("Error: '{script_detached}' file not found.");
-                            continue;
-                        }
+                let mut read = ReadBuf::new(&mut buf);
+                match Pin::new(&mut this.stream).poll_read(cx, &mut read) {
+                    Poll::Ready(Ok(())) if read.filled().is_empty() => {
+                        this.done = true;
+                        let closed = io::Error::new(io::ErrorKind::UnexpectedEof, "Hyprland socket closed");
+                        return Poll::Ready(Some(Err(closed)));
                     }
+                    Poll::Ready(Ok(())) => this.framer.push(read.filled()),
+                    Poll::Ready(Err(e)) => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    Poll::Pending => return Poll::Pending,
                 }
-                .metadata()
-                .unwrap();
-                let permissions = metadata.permissions();
-                if !permissions.mode() & 0o100 != 0 {
-                    eprintln!("Error: '{script_detached}' file is not executable.");
-                    continue;
-                }
-                Command::new(script_detached)
-                    .args([data_parts[1]])
-                    .spawn()
-                    .expect("Failed to execute command");
             }
         }
     }
 }
 
+// Wait until monitors stop changing: no monitoradded/monitorremoved events
+// and no change in the connected set for `quiet`, or until `timeout` runs
+// out. Lets the daemon started from exec-once skip assigning workspaces to
+// the one monitor that happened to be up first.
+pub fn settle<F>(socket_addr: &str, quiet: Duration, timeout: Duration, connected: F) -> std::io::Result<()>
+where
+    F: Fn() -> Vec<String>,
+{
+    let mut stream = connect(socket_addr)?;
+    // Wake up regularly to poll the connected set and check the deadlines
+    stream.set_read_timeout(Some(Duration::from_millis(100)))?;
+
+    let start = Instant::now();
+    let mut last_change = start;
+    let mut monitors = connected();
+    let mut framer = Framer::new();
+    let mut buf = [0u8; 4096];
+
+    while last_change.elapsed() < quiet {
+        if start.elapsed() >= timeout {
+            eprintln!("Monitors still changing after {:?}, assigning anyway", timeout);
+            return Ok(());
+        }
+
+        match stream.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => framer.push(&buf[..n]),
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+        while let Some(event) = framer.next_event() {
+            if event.name.starts_with("monitoradded") || event.name.starts_with("monitorremoved") {
+                last_change = Instant::now();
+            }
+        }
+
+        let current = connected();
+        if current != monitors {
+            monitors = current;
+            last_change = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+// Signatures of running Hyprland instances, sorted
+pub fn list_instances() -> Vec<String> {
+    let mut dirs = vec!["/tmp/hypr".to_string()];
+    if let Ok(runtime_dir) = env::var("XDG_RUNTIME_DIR") {
+        dirs.insert(0, format!("{}/hypr", runtime_dir));
+    }
+
+    let mut instances = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let signature = entry.file_name().to_string_lossy().to_string();
+            if entry.path().join(".socket2.sock").exists() && !instances.contains(&signature) {
+                instances.push(signature);
+            }
+        }
+    }
+    instances.sort();
+    instances
+}
+
 // Get Hyprland socket path
 pub fn get_hyprland_socket() -> Result<String, String> {
-    let hypr_inst = env::var("HYPRLAND_INSTANCE_SIGNATURE")
-        .map_err(|e| format!("Fatal Error: Hyprland is not running. {}", e))?;
+    get_socket(".socket2.sock")
+}
+
+// Get Hyprland request socket path (the one hyprctl talks to)
+pub fn get_hyprland_request_socket() -> Result<String, String> {
+    get_socket(".socket.sock")
+}
+
+fn get_socket(name: &str) -> Result<String, String> {
+    // The instance the context selected, else the one we run under
+    let hypr_inst = match context::instance() {
+        Some(signature) => signature.to_string(),
+        None => env::var("HYPRLAND_INSTANCE_SIGNATURE")
+            .map_err(|e| format!("Fatal Error: Hyprland is not running. {}", e))?,
+    };
 
-    let default_socket = format!("/tmp/hypr/{}/.socket2.sock", hypr_inst);
+    let default_socket = format!("/tmp/hypr/{}/{}", hypr_inst, name);
     
     // Check if socket is in $XDG_RUNTIME_DIR/hypr first, then fall back
     Ok(match env::var("XDG_RUNTIME_DIR") {
         Ok(runtime_dir) => {
-            let path = format!("{}/hypr/{}/.socket2.sock", runtime_dir, hypr_inst);
+            let path = format!("{}/hypr/{}/{}", runtime_dir, hypr_inst, name);
             if std::fs::metadata(&path).is_ok() {
                 path
             } else {
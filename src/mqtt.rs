@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::config::Mqtt;
+use crate::get_monitors;
+
+// socket2 events after which the published state is refreshed
+pub const EVENTS: [&str; 8] = [
+    "workspace",
+    "focusedmon",
+    "monitoradded",
+    "monitorremoved",
+    "moveworkspace",
+    "renameworkspace",
+    "activespecial",
+    "destroyworkspace",
+];
+
+// Keep-alive announced to the broker, in seconds; pings go out at half of it
+const KEEP_ALIVE: u16 = 60;
+
+// Connect and read timeout for the broker
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Shortest wait before connecting again after losing the broker
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+// Packet types (fixed header, upper nibble) of MQTT 3.1.1
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PINGREQ: u8 = 0xc0;
+
+// Variable length "remaining length" of the fixed header
+fn remaining_length(mut length: usize, packet: &mut Vec<u8>) {
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+}
+
+// Length-prefixed string or binary field
+fn field(bytes: &[u8], body: &mut Vec<u8>) {
+    body.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    body.extend_from_slice(bytes);
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    remaining_length(body.len(), &mut packet);
+    packet.extend_from_slice(body);
+    packet
+}
+
+// CONNECT with a clean session and an "offline" will on the status topic
+fn connect_packet(mqtt: &Mqtt) -> Vec<u8> {
+    // Clean session, will flag and will retain
+    let mut flags = 0x02 | 0x04 | 0x20;
+    if mqtt.username.is_some() {
+        flags |= 0x80;
+    }
+    if mqtt.password.is_some() {
+        flags |= 0x40;
+    }
+    let mut body = Vec::new();
+    field(b"MQTT", &mut body);
+    body.push(4);
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE.to_be_bytes());
+    field(mqtt.client_id().as_bytes(), &mut body);
+    field(format!("{}/status", mqtt.topic()).as_bytes(), &mut body);
+    field(b"offline", &mut body);
+    if let Some(username) = &mqtt.username {
+        field(username.as_bytes(), &mut body);
+    }
+    if let Some(password) = &mqtt.password {
+        field(password.as_bytes(), &mut body);
+    }
+    packet(CONNECT, &body)
+}
+
+fn connect(mqtt: &Mqtt) -> io::Result<TcpStream> {
+    let address = (mqtt.host.as_str(), mqtt.port())
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} didn't resolve", mqtt.host)))?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.write_all(&connect_packet(mqtt))?;
+
+    let mut connack = [0; 4];
+    stream.read_exact(&mut connack)?;
+    if connack[0] != CONNACK || connack[3] != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("broker refused the connection (code {})", connack[3]),
+        ));
+    }
+    Ok(stream)
+}
+
+// Retained QoS 0 publish; an empty payload clears the topic
+fn publish_packet(topic: &str, payload: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    field(topic.as_bytes(), &mut body);
+    body.extend_from_slice(payload.as_bytes());
+    packet(PUBLISH | 0x01, &body)
+}
+
+fn publish(stream: &mut TcpStream, topic: &str, payload: &str) -> io::Result<()> {
+    stream.write_all(&publish_packet(topic, payload))
+}
+
+// Keep the connection up; the PINGRESP is drained so it doesn't pile up
+fn ping(stream: &mut TcpStream) -> io::Result<()> {
+    stream.write_all(&[PINGREQ, 0])?;
+    let mut response = [0; 2];
+    stream.read_exact(&mut response)
+}
+
+// Per-monitor payloads keyed by topic
+fn state(mqtt: &Mqtt) -> HashMap<String, String> {
+    get_monitors()
+        .iter()
+        .map(|m| {
+            let workspace = m.active_workspace.id;
+            let special = m.special_workspace.name.trim_start_matches("special:");
+            let payload = serde_json::json!({
+                "workspace": workspace,
+                "name": m.active_workspace.name,
+                "group": (workspace > 0).then(|| (workspace - 1) % 10 + 1),
+                "focused": m.focused,
+                "special": (!special.is_empty()).then_some(special),
+                "description": m.description,
+            });
+            (format!("{}/monitor/{}", mqtt.topic(), m.name), payload.to_string())
+        })
+        .collect()
+}
+
+// Publish what changed since the last call; topics of monitors that are
+// gone are cleared
+fn sync(stream: &mut TcpStream, mqtt: &Mqtt, published: &mut HashMap<String, String>) -> io::Result<()> {
+    let current = state(mqtt);
+    for topic in published.keys().filter(|t| !current.contains_key(*t)) {
+        publish(stream, topic, "")?;
+    }
+    for (topic, payload) in &current {
+        if published.get(topic) != Some(payload) {
+            publish(stream, topic, payload)?;
+        }
+    }
+    *published = current;
+    Ok(())
+}
+
+// The daemon's MQTT thread: each message on `changes` republishes the
+// monitors whose state changed. A lost broker is reconnected on the next
+// change, no sooner than RECONNECT_DELAY after the last attempt.
+pub fn publish_state(changes: mpsc::Receiver<()>, mqtt: &Mqtt) {
+    let mut stream: Option<TcpStream> = None;
+    let mut published = HashMap::new();
+    let mut last_attempt: Option<Instant> = None;
+    let mut changed = true;
+
+    loop {
+        if stream.is_none() && changed && last_attempt.is_none_or(|t| t.elapsed() >= RECONNECT_DELAY) {
+            last_attempt = Some(Instant::now());
+            match connect(mqtt) {
+                Ok(mut connected) => match publish(&mut connected, &format!("{}/status", mqtt.topic()), "online") {
+                    Ok(()) => {
+                        println!("Connected to MQTT broker {}:{}", mqtt.host, mqtt.port());
+                        published.clear();
+                        stream = Some(connected);
+                    }
+                    Err(e) => eprintln!("MQTT broker {}: {}", mqtt.host, e),
+                },
+                Err(e) => eprintln!("MQTT broker {}: {}", mqtt.host, e),
+            }
+        }
+        if let Some(connected) = stream.as_mut().filter(|_| changed) {
+            match sync(connected, mqtt, &mut published) {
+                Ok(()) => changed = false,
+                Err(e) => {
+                    eprintln!("Lost MQTT broker {}: {}", mqtt.host, e);
+                    stream = None;
+                }
+            }
+        }
+
+        match changes.recv_timeout(Duration::from_secs(KEEP_ALIVE as u64 / 2)) {
+            Ok(()) => changed = true,
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(connected) = stream.as_mut() {
+                    if let Err(e) = ping(connected) {
+                        eprintln!("Lost MQTT broker {}: {}", mqtt.host, e);
+                        stream = None;
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mqtt(toml: &str) -> Mqtt {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn encodes_remaining_length() {
+        for (length, expected) in [
+            (0, vec![0x00]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (16383, vec![0xff, 0x7f]),
+            (16384, vec![0x80, 0x80, 0x01]),
+        ] {
+            let mut encoded = Vec::new();
+            remaining_length(length, &mut encoded);
+            assert_eq!(encoded, expected, "length {}", length);
+        }
+    }
+
+    #[test]
+    fn encodes_connect() {
+        let mut expected = vec![CONNECT, 42, 0, 4];
+        expected.extend_from_slice(b"MQTT");
+        // Protocol level 4, clean session + will + will retain, keep-alive 60
+        expected.extend_from_slice(&[4, 0x26, 0, 60]);
+        expected.extend_from_slice(b"\0\x06hyprws\0\x0dhyprws/status\0\x07offline");
+        assert_eq!(connect_packet(&mqtt("host = \"broker\"")), expected);
+    }
+
+    #[test]
+    fn encodes_connect_with_credentials() {
+        let packet = connect_packet(&mqtt(
+            "host = \"broker\"\ntopic = \"home/desk/\"\nclient_id = \"desk\"\nusername = \"u\"\npassword = \"p\"",
+        ));
+        let mut expected = vec![CONNECT, 49, 0, 4];
+        expected.extend_from_slice(b"MQTT");
+        expected.extend_from_slice(&[4, 0xe6, 0, 60]);
+        expected.extend_from_slice(b"\0\x04desk\0\x10home/desk/status\0\x07offline\0\x01u\0\x01p");
+        assert_eq!(packet, expected);
+    }
+
+    #[test]
+    fn encodes_publish() {
+        let mut expected = vec![PUBLISH | 0x01, 21, 0, 13];
+        expected.extend_from_slice(b"hyprws/statusonline");
+        assert_eq!(publish_packet("hyprws/status", "online"), expected);
+
+        // Clearing a topic sends an empty payload
+        assert_eq!(publish_packet("t", ""), vec![PUBLISH | 0x01, 3, 0, 1, b't']);
+
+        // Long payloads take a two-byte remaining length
+        let payload = "x".repeat(200);
+        let packet = publish_packet("t", &payload);
+        assert_eq!(&packet[..5], &[PUBLISH | 0x01, 0xcb, 0x01, 0, 1]);
+        assert_eq!(packet.len(), 3 + 203);
+    }
+}
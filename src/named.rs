@@ -0,0 +1,137 @@
+use std::sync::Mutex;
+
+use crate::config::Config;
+use crate::{
+    dispatch_batch, instance_suffix, move_silent_workspace, move_workspace, named_workspace_rules, special,
+    switch_workspace, WorkspaceMonitorMap,
+};
+
+/// Rules binding named and special workspaces to monitors, read on first use
+/// and kept until Hyprland reloads its config
+static RULES: Mutex<Option<Vec<WorkspaceMonitorMap>>> = Mutex::new(None);
+
+// A workspace argument that isn't a group: Hyprland's named and special
+// workspaces
+#[derive(Debug, PartialEq)]
+pub enum Named {
+    // "name:mail"
+    Name(String),
+    // "special" or "special:scratch"
+    Special(Option<String>),
+}
+
+pub fn parse(arg: &str) -> Option<Named> {
+    if let Some(name) = arg.strip_prefix("name:") {
+        return Some(Named::Name(name.to_string()));
+    }
+    match arg.strip_prefix("special") {
+        Some("") => Some(Named::Special(None)),
+        Some(rest) => rest.strip_prefix(':').map(|name| Named::Special(Some(name.to_string()))),
+        None => None,
+    }
+}
+
+impl Named {
+    // The workspace as a dispatcher argument
+    fn dispatch_name(&self) -> String {
+        match self {
+            Named::Name(name) => format!("name:{}", name),
+            Named::Special(Some(name)) => format!("special:{}", name),
+            Named::Special(None) => "special".to_string(),
+        }
+    }
+}
+
+// Monitor a `workspace = name:NAME, monitor:MONITOR` (or special:NAME) rule
+// binds the workspace to. Such rules usually live in hyprland.conf, so
+// they're taken from what Hyprland loaded.
+fn monitor_rule(named: &Named, config: &Config) -> Option<String> {
+    let mut rules = RULES.lock().unwrap_or_else(|e| e.into_inner());
+    let rules = rules.get_or_insert_with(|| named_workspace_rules(&config.ws_conf_path(&instance_suffix())));
+    let workspace = named.dispatch_name();
+    rules
+        .iter()
+        .find(|r| r.name.as_deref() == Some(workspace.as_str()))
+        .map(|r| r.monitor.clone())
+}
+
+// Read the rules again on next use, after Hyprland reloaded its config
+pub fn forget_rules() {
+    *RULES.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+// The numbered workspace whose defaultName is `name`, if any; it's switched
+// to like its group
+fn numbered(name: &str, maps: &[WorkspaceMonitorMap]) -> Option<i32> {
+    maps.iter()
+        .find(|m| m.name.as_deref() == Some(name))
+        .map(|m| m.workspace)
+}
+
+// Switch to a named workspace on the monitor its rule gives, or toggle a
+// special workspace
+pub fn switch(named: &Named, maps: &[WorkspaceMonitorMap], config: &Config) {
+    match named {
+        Named::Name(name) => {
+            if let Some(workspace) = numbered(name, maps) {
+                switch_workspace(workspace, maps, config);
+                return;
+            }
+            let mut dispatches = Vec::new();
+            if let Some(monitor) = monitor_rule(named, config) {
+                dispatches.push(format!("focusmonitor {}", monitor));
+            }
+            dispatches.push(format!("workspace {}", named.dispatch_name()));
+            dispatch_batch(&dispatches);
+        }
+        Named::Special(name) => {
+            // Shown on the rule's monitor rather than the focused one
+            if let Some(monitor) = monitor_rule(named, config) {
+                dispatch_batch(&[format!("focusmonitor {}", monitor)]);
+            }
+            special::toggle(name.as_deref());
+        }
+    }
+}
+
+// Move the active window (or with `silent` only the window) to a named or
+// special workspace
+pub fn move_window(named: &Named, silent: bool, maps: &[WorkspaceMonitorMap], config: &Config) {
+    if let Named::Name(name) = named {
+        if let Some(workspace) = numbered(name, maps) {
+            if silent {
+                move_silent_workspace(workspace, maps, config);
+            } else {
+                move_workspace(workspace, maps, config);
+            }
+            return;
+        }
+    }
+    let dispatcher = if silent { "movetoworkspacesilent" } else { "movetoworkspace" };
+    dispatch_batch(&[format!("{} {}", dispatcher, named.dispatch_name())]);
+    if matches!(named, Named::Special(_)) {
+        special::save(&special::query());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_and_special_workspaces() {
+        assert_eq!(parse("name:mail"), Some(Named::Name("mail".to_string())));
+        assert_eq!(parse("special"), Some(Named::Special(None)));
+        assert_eq!(parse("special:scratch"), Some(Named::Special(Some("scratch".to_string()))));
+        for other in ["3", "e+1", "specials", "mail", ""] {
+            assert_eq!(parse(other), None, "'{}' isn't named", other);
+        }
+    }
+
+    #[test]
+    fn dispatch_name_round_trips() {
+        for arg in ["name:mail", "special", "special:scratch"] {
+            assert_eq!(parse(arg).unwrap().dispatch_name(), arg);
+        }
+    }
+}
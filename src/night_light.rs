@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs;
+use std::process::{Command, Stdio};
+
+use crate::config::{Config, NightLightBackend};
+use crate::{instance_suffix, process, role, runtime_path, write_runtime_file};
+
+// A night_light value: Some(Kelvin), or None for "off"
+pub fn parse(value: &str) -> Result<Option<u32>, String> {
+    if value == "off" {
+        return Ok(None);
+    }
+    match value.parse() {
+        Ok(kelvin @ 1000..=20000) => Ok(Some(kelvin)),
+        _ => Err(format!("expected a temperature in Kelvin (1000-20000) or \"off\", got \"{}\"", value)),
+    }
+}
+
+// The single setting used when monitors disagree: the warmest temperature
+// asked for, or off when every monitor is off
+fn warmest(temperatures: impl Iterator<Item = Option<u32>>) -> Option<u32> {
+    temperatures.flatten().min()
+}
+
+// Whether a profile's night_light gives monitors different temperatures,
+// which backends tinting every monitor alike can't do
+pub fn differs(settings: &HashMap<String, String>) -> bool {
+    let mut temperatures = settings.values().filter_map(|value| parse(value).ok());
+    temperatures.next().is_some_and(|first| temperatures.any(|t| t != first))
+}
+
+// Start a long-running tool without waiting on it
+fn spawn(command: &mut Command) -> Option<u32> {
+    match process::spawn(command.stdout(Stdio::null())) {
+        Ok(pid) => Some(pid),
+        Err(e) => {
+            eprintln!("Failed to run {:?}: {}", command.get_program(), e);
+            None
+        }
+    }
+}
+
+// Runtime file with the PID of the gammastep hyprws started last
+fn gammastep_pid_file() -> String {
+    format!("gammastep{}.pid", instance_suffix())
+}
+
+// Stop the gammastep hyprws started, leaving any others the user runs alone
+fn stop_gammastep() {
+    let path = runtime_path(&gammastep_pid_file());
+    let Some(pid) = fs::read_to_string(&path).ok().and_then(|pid| pid.trim().parse::<u32>().ok()) else {
+        return;
+    };
+    let _ = fs::remove_file(&path);
+    // The PID may have gone to another process since
+    if fs::read_to_string(format!("/proc/{}/comm", pid)).is_ok_and(|comm| comm.trim() == "gammastep") {
+        process::run("kill", &[&pid.to_string()]);
+    }
+}
+
+// Set a profile's night light, keyed by monitor (or role). Roles that
+// aren't connected are skipped with a warning. The tools that tint every monitor alike get
+// the warmest setting when the connected monitors disagree.
+pub fn apply(settings: &HashMap<String, String>, backend: NightLightBackend, config: &Config) {
+    let temperatures: Vec<(String, Option<u32>)> = settings
+        .iter()
+        .filter_map(|(monitor, value)| Some((role::resolve_or_warn(monitor, config)?, parse(value).ok()?)))
+        .collect();
+    let Some((_, first)) = temperatures.first() else {
+        return;
+    };
+
+    if backend == NightLightBackend::Command {
+        let command = config.night_light_command.as_deref().unwrap_or_default();
+        for (monitor, temperature) in &temperatures {
+            let temperature = temperature.map_or("off".to_string(), |k| k.to_string());
+            spawn(
+                process::command("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("HYPRWS_MONITOR", monitor)
+                    .env("HYPRWS_TEMPERATURE", temperature),
+            );
+        }
+        return;
+    }
+
+    let mut temperature = *first;
+    if temperatures.iter().any(|(_, t)| t != first) {
+        temperature = warmest(temperatures.iter().map(|(_, t)| *t));
+        eprintln!(
+            "Warning: {:?} tints every monitor alike, but night_light differs between them; \
+             using {} for all (night_light_backend = \"command\" sets them apart)",
+            backend,
+            temperature.map_or("off".to_string(), |k| format!("{}K", k))
+        );
+    }
+
+    match (backend, temperature) {
+        (NightLightBackend::Hyprsunset, Some(kelvin)) => {
+            process::run("hyprctl", &["hyprsunset", "temperature", &kelvin.to_string()])
+        }
+        (NightLightBackend::Hyprsunset, None) => process::run("hyprctl", &["hyprsunset", "identity"]),
+        (NightLightBackend::Gammastep, temperature) => {
+            // gammastep keeps its tint only while running, so replace the last one
+            stop_gammastep();
+            let Some(kelvin) = temperature else {
+                return;
+            };
+            let Some(pid) = spawn(Command::new("gammastep").args(["-P", "-O", &kelvin.to_string()])) else {
+                return;
+            };
+            if let Err(e) = write_runtime_file(&gammastep_pid_file(), &pid.to_string()) {
+                eprintln!("Warning: couldn't record the gammastep PID: {}", e);
+            }
+        }
+        (NightLightBackend::Command, _) => {}
+    }
+}
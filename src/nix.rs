@@ -0,0 +1,89 @@
+use std::io;
+
+use crate::config;
+
+// A value as a Nix expression, indented to `depth` levels
+fn to_nix(value: &toml::Value, depth: usize) -> String {
+    let indent = "  ".repeat(depth + 1);
+    let close = "  ".repeat(depth);
+    match value {
+        toml::Value::String(s) => string(s),
+        toml::Value::Integer(i) => i.to_string(),
+        // Debug keeps the decimal point, so 3.0 stays a float
+        toml::Value::Float(f) => format!("{:?}", f),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(d) => string(&d.to_string()),
+        toml::Value::Array(items) if items.is_empty() => "[ ]".to_string(),
+        toml::Value::Array(items) => {
+            let items: String = items
+                .iter()
+                .map(|item| format!("{}{}\n", indent, to_nix(item, depth + 1)))
+                .collect();
+            format!("[\n{}{}]", items, close)
+        }
+        toml::Value::Table(table) if table.is_empty() => "{ }".to_string(),
+        toml::Value::Table(table) => {
+            let attrs: String = table
+                .iter()
+                .map(|(key, value)| format!("{}{} = {};\n", indent, attr_name(key), to_nix(value, depth + 1)))
+                .collect();
+            format!("{{\n{}{}}}", attrs, close)
+        }
+    }
+}
+
+fn string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+// Keys that aren't plain identifiers (e.g. "1" in [names]) need quoting
+fn attr_name(key: &str) -> String {
+    let mut chars = key.chars();
+    let plain = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '\'' | '-'));
+    if plain {
+        key.to_string()
+    } else {
+        string(key)
+    }
+}
+
+// The current config as a home-manager module: config.toml generated from
+// the settings, plus a user service running the daemon
+pub fn generate_module() -> io::Result<String> {
+    let settings = config::raw_table()?;
+    Ok(format!(
+        r#"{{ pkgs, ... }}:
+let
+  # Adjust if hyprws is packaged under another name
+  hyprws = pkgs.hyprws;
+in
+{{
+  home.packages = [ hyprws ];
+
+  xdg.configFile."hyprws/config.toml".source = (pkgs.formats.toml {{ }}).generate "hyprws-config" {};
+
+  systemd.user.services.hyprws = {{
+    Unit = {{
+      Description = "Per-monitor workspaces for Hyprland";
+      PartOf = [ "graphical-session.target" ];
+      After = [ "graphical-session.target" ];
+    }};
+    Service = {{
+      Type = "notify";
+      ExecStart = "${{hyprws}}/bin/hyprws daemon --yes";
+      Restart = "on-failure";
+      WatchdogSec = 30;
+    }};
+    Install.WantedBy = [ "graphical-session.target" ];
+  }};
+}}
+"#,
+        to_nix(&toml::Value::Table(settings), 1)
+    ))
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use serde::{Deserialize, Serialize};
+
+use crate::{create_runtime_dir, get_clients, instance_suffix, runtime_path, HyprlandClient, HyprlandWorkspaceRef};
+
+// What occupancy counts need to know about a window
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Window {
+    workspace: i32,
+    workspace_name: String,
+    class: String,
+    floating: bool,
+    fullscreen: bool,
+}
+
+// Windows per workspace, kept up to date by the daemon from socket2 events
+// and shared with CLI invocations through a runtime file, so they can skip
+// the clients query
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Occupancy {
+    // Daemon that keeps the file current
+    pid: u32,
+    // Keyed by address without the 0x prefix, as in events
+    windows: HashMap<String, Window>,
+    #[serde(skip)]
+    active: String,
+}
+
+fn cache_path() -> String {
+    runtime_path(&format!("occupancy{}.json", instance_suffix()))
+}
+
+impl Occupancy {
+    // Start from a full clients query
+    pub fn seed() -> Self {
+        let mut occupancy = Occupancy {
+            pid: std::process::id(),
+            ..Default::default()
+        };
+        occupancy.reseed();
+        occupancy
+    }
+
+    pub fn reseed(&mut self) {
+        self.windows = get_clients()
+            .into_iter()
+            .map(|c| {
+                let window = Window {
+                    workspace: c.workspace.id,
+                    fullscreen: c.is_fullscreen(),
+                    workspace_name: c.workspace.name,
+                    class: c.class,
+                    floating: c.floating,
+                };
+                (c.address.trim_start_matches("0x").to_string(), window)
+            })
+            .collect();
+    }
+
+    // Update from an event; returns whether anything changed
+    pub fn apply(&mut self, event: &str, data: &str) -> bool {
+        match event {
+            // openwindow>>ADDRESS,WORKSPACENAME,CLASS,TITLE
+            "openwindow" => {
+                let mut parts = data.splitn(4, ',');
+                let (Some(address), Some(name), Some(class)) = (parts.next(), parts.next(), parts.next()) else {
+                    return false;
+                };
+                let window = Window {
+                    workspace: name.parse().unwrap_or(-1),
+                    workspace_name: name.to_string(),
+                    class: class.to_string(),
+                    floating: false,
+                    fullscreen: false,
+                };
+                self.windows.insert(address.to_string(), window);
+                true
+            }
+            "closewindow" => self.windows.remove(data).is_some(),
+            // movewindowv2>>ADDRESS,WORKSPACEID,WORKSPACENAME
+            "movewindowv2" => {
+                let mut parts = data.splitn(3, ',');
+                let (Some(address), Some(id), Some(name)) = (parts.next(), parts.next(), parts.next()) else {
+                    return false;
+                };
+                let (Some(window), Ok(id)) = (self.windows.get_mut(address), id.parse()) else {
+                    return false;
+                };
+                window.workspace = id;
+                window.workspace_name = name.to_string();
+                true
+            }
+            // changefloatingmode>>ADDRESS,FLOATING
+            "changefloatingmode" => {
+                let Some((address, floating)) = data.split_once(',') else {
+                    return false;
+                };
+                let Some(window) = self.windows.get_mut(address) else {
+                    return false;
+                };
+                window.floating = floating == "1";
+                true
+            }
+            "activewindowv2" => {
+                self.active = data.to_string();
+                false
+            }
+            // fullscreen>>0|1 applies to the active window
+            "fullscreen" => {
+                let Some(window) = self.windows.get_mut(&self.active) else {
+                    return false;
+                };
+                window.fullscreen = data == "1";
+                true
+            }
+            // Windows may have been moved around without per-window events
+            "monitoradded" | "monitorremoved" | "configreloaded" => {
+                self.reseed();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // The cache in the shape of a clients query
+    pub fn clients(&self) -> Vec<HyprlandClient> {
+        self.windows
+            .iter()
+            .map(|(address, w)| HyprlandClient {
+                address: format!("0x{}", address),
+                class: w.class.clone(),
+                title: String::new(),
+                monitor: -1,
+                workspace: HyprlandWorkspaceRef {
+                    id: w.workspace,
+                    name: w.workspace_name.clone(),
+                },
+                floating: w.floating,
+                fullscreen: serde_json::Value::Bool(w.fullscreen),
+                grouped: Vec::new(),
+            })
+            .collect()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = cache_path();
+        create_runtime_dir()?;
+        let json = serde_json::to_string(self).map_err(io::Error::other)?;
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+}
+
+// Whether a daemon is keeping the cache current
+pub fn daemon_running() -> bool {
+    cached_clients().is_some()
+}
+
+// Clients from the daemon's cache, if a daemon is keeping it current
+pub fn cached_clients() -> Option<Vec<HyprlandClient>> {
+    let json = fs::read_to_string(cache_path()).ok()?;
+    let occupancy: Occupancy = serde_json::from_str(&json).ok()?;
+    is_hyprws(occupancy.pid).then(|| occupancy.clients())
+}
+
+// Whether PID is a hyprws process, not just any process that reused the PID
+// of a daemon that crashed
+fn is_hyprws(pid: u32) -> bool {
+    let comm = |pid: &str| fs::read_to_string(format!("/proc/{}/comm", pid)).ok();
+    comm(&pid.to_string()).is_some_and(|theirs| comm("self").is_some_and(|ours| theirs == ours))
+}
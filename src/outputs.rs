@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::config::{Config, OutputRule};
+use crate::{get_monitors, role, MAX_MONITORS};
+
+// What [[outputs]] rules change about an assignment
+#[derive(Default, Debug)]
+pub struct Outputs {
+    // First and last slot (1-10) of the block, for monitors with fixed workspaces
+    pub slots: HashMap<String, (usize, usize)>,
+    // `monitor =` values for mirrored monitors, written to ws.conf
+    pub mirrors: Vec<String>,
+}
+
+// Apply [[outputs]] to (block, monitor) pairs: mirrored monitors drop out,
+// monitors with fixed workspaces take that block, and the others keep theirs
+// or move to the first free block when a fixed one took it
+pub fn apply(blocks: &[(usize, String)], config: &Config) -> Result<(Vec<(usize, String)>, Outputs), String> {
+    if config.outputs.is_empty() {
+        return Ok((blocks.to_vec(), Outputs::default()));
+    }
+
+    // Monitors Hyprland doesn't answer for can still match by name
+    let monitors = get_monitors();
+    arrange(blocks, config, |name| {
+        monitors
+            .iter()
+            .find(|m| m.name == name)
+            .map_or((String::new(), String::new()), |m| (m.description.clone(), m.serial.clone()))
+    })
+}
+
+// apply, with the description and serial of a connector from `identify`
+fn arrange<F>(blocks: &[(usize, String)], config: &Config, identify: F) -> Result<(Vec<(usize, String)>, Outputs), String>
+where
+    F: Fn(&str) -> (String, String),
+{
+    let rule_for = |name: &str| -> Option<&OutputRule> {
+        let (description, serial) = identify(name);
+        config.outputs.iter().find(|rule| rule.matches(name, &description, &serial))
+    };
+
+    let mut outputs = Outputs::default();
+    let mut placed: Vec<(usize, String)> = Vec::new();
+    let mut others = Vec::new();
+    for (block, name) in blocks {
+        let rule = rule_for(name);
+        // A mirror of a role that isn't connected is placed like any monitor
+        let mirror = rule.and_then(|r| r.mirror.as_ref());
+        if let Some(target) = mirror.and_then(|m| role::resolve_or_warn(m, config)) {
+            outputs.mirrors.push(format!("{}, preferred, auto, 1, mirror, {}", name, target));
+            continue;
+        }
+        match rule.and_then(|r| r.range()) {
+            Some((first, last)) if !placed.iter().any(|(b, _)| *b == (first - 1) / 10) => {
+                let fixed = (first - 1) / 10;
+                outputs.slots.insert(name.clone(), (first - fixed * 10, last - fixed * 10));
+                placed.push((fixed, name.clone()));
+            }
+            Some((first, _)) => {
+                eprintln!("Warning: workspace {} is already fixed to another monitor, placing {} as usual", first, name);
+                others.push((*block, name.clone()));
+            }
+            None => others.push((*block, name.clone())),
+        }
+    }
+
+    for (block, name) in others {
+        let taken = |b: &usize| placed.iter().any(|(p, _)| p == b);
+        let block = if taken(&block) {
+            (0..MAX_MONITORS)
+                .find(|b| !taken(b))
+                .ok_or_else(|| format!("No free block of workspaces left for {}", name))?
+        } else {
+            block
+        };
+        placed.push((block, name));
+    }
+    placed.sort_by_key(|(b, _)| *b);
+    Ok((placed, outputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(outputs: &str) -> Config {
+        toml::from_str(outputs).unwrap()
+    }
+
+    fn blocks(names: &[&str]) -> Vec<(usize, String)> {
+        names.iter().enumerate().map(|(b, n)| (b, n.to_string())).collect()
+    }
+
+    fn arranged(blocks: &[(usize, String)], config: &Config) -> Result<(Vec<(usize, String)>, Outputs), String> {
+        arrange(blocks, config, |name| match name {
+            "DP-2" => ("Dell Inc. U2720Q".to_string(), "ABC123".to_string()),
+            _ => (String::new(), String::new()),
+        })
+    }
+
+    #[test]
+    fn without_rules_blocks_stay() {
+        let blocks = blocks(&["eDP-1", "DP-1"]);
+        assert_eq!(arranged(&blocks, &config("")).unwrap().0, blocks);
+    }
+
+    #[test]
+    fn fixed_workspaces_take_their_block() {
+        let config = config("[[outputs]]\ndescription = \"U2720Q\"\nworkspaces = \"1-5\"");
+        let (placed, outputs) = arranged(&blocks(&["eDP-1", "DP-2"]), &config).unwrap();
+        // eDP-1 gives up block 0 and takes the first free one
+        assert_eq!(placed, [(0, "DP-2".to_string()), (1, "eDP-1".to_string())]);
+        assert_eq!(outputs.slots.get("DP-2"), Some(&(1, 5)));
+        assert!(outputs.mirrors.is_empty());
+    }
+
+    #[test]
+    fn mirrors_drop_out() {
+        let config = config("[[outputs]]\nserial = \"ABC123\"\nmirror = \"eDP-1\"");
+        let (placed, outputs) = arranged(&blocks(&["eDP-1", "DP-2"]), &config).unwrap();
+        assert_eq!(placed, [(0, "eDP-1".to_string())]);
+        assert_eq!(outputs.mirrors, ["DP-2, preferred, auto, 1, mirror, eDP-1"]);
+    }
+
+    #[test]
+    fn runs_out_of_blocks() {
+        let names: Vec<String> = (0..MAX_MONITORS).map(|i| format!("HDMI-A-{}", i)).collect();
+        let mut blocks: Vec<(usize, String)> = names.iter().cloned().enumerate().collect();
+        blocks.push((0, "DP-2".to_string()));
+        let config = config("[[outputs]]\nname = \"DP-2\"\nworkspaces = \"1-10\"");
+        assert!(arranged(&blocks, &config).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use std::env;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+use crate::context;
+
+// Home directory from /etc/passwd, for when HOME isn't set (e.g. some
+// service managers)
+fn passwd_home() -> Option<String> {
+    let uid = fs::metadata("/proc/self").ok()?.uid().to_string();
+    fs::read_to_string("/etc/passwd")
+        .ok()?
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() > 5 && fields[2] == uid)
+        .map(|fields| fields[5].to_string())
+}
+
+pub fn home() -> String {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() => home,
+        _ => passwd_home().unwrap_or_else(|| "/".to_string()),
+    }
+}
+
+// ~/ or $HOME/ at the start of a path replaced by the home directory
+pub fn expand_home(path: &str) -> String {
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("$HOME/")) {
+        Some(rest) => format!("{}/{}", home(), rest),
+        None => path.to_string(),
+    }
+}
+
+// An XDG base directory, or its default under the home directory
+fn xdg_dir(variable: &str, default: &str) -> String {
+    match env::var(variable) {
+        Ok(dir) if !dir.is_empty() => dir,
+        _ => format!("{}/{}", home(), default),
+    }
+}
+
+// Non-empty environment variable, with ~ expanded
+fn env_path(variable: &str) -> Option<String> {
+    env::var(variable).ok().filter(|v| !v.is_empty()).map(|v| expand_home(&v))
+}
+
+// Where config.toml (or .yaml / .json) is looked for
+pub fn config_dir() -> String {
+    format!("{}/hyprws", xdg_dir("XDG_CONFIG_HOME", ".config"))
+}
+
+// Hyprland's config directory, home of hyprland.conf and ws.conf
+pub fn hypr_dir() -> String {
+    format!("{}/hypr", xdg_dir("XDG_CONFIG_HOME", ".config"))
+}
+
+pub fn hyprland_conf() -> String {
+    format!("{}/hyprland.conf", hypr_dir())
+}
+
+// Config file chosen with --config or HYPRWS_CONFIG instead of searching
+// config_dir()
+pub fn config_override() -> Option<String> {
+    context::config_file().map(str::to_string).or_else(|| env_path("HYPRWS_CONFIG"))
+}
+
+// monitors.json, thumbnails and bundle staging: --cache-dir,
+// HYPRWS_CACHE_DIR, or hyprws under $XDG_CACHE_HOME (~/.cache)
+pub fn cache_dir() -> String {
+    context::cache_dir()
+        .map(str::to_string)
+        .or_else(|| env_path("HYPRWS_CACHE_DIR"))
+        .unwrap_or_else(|| format!("{}/hyprws", xdg_dir("XDG_CACHE_HOME", ".cache")))
+}
+
+// Persisted daemon state: $XDG_STATE_HOME/hyprws (~/.local/state/hyprws)
+pub fn state_dir() -> String {
+    format!("{}/hyprws", xdg_dir("XDG_STATE_HOME", ".local/state"))
+}
+
+// --config / --cache-dir as given to this process, for re-running hyprws
+// (the environment variables are inherited anyway)
+pub fn flags() -> Vec<String> {
+    let mut flags = Vec::new();
+    if let Some(path) = context::config_file() {
+        flags.extend(["--config".to_string(), path.to_string()]);
+    }
+    if let Some(dir) = context::cache_dir() {
+        flags.extend(["--cache-dir".to_string(), dir.to_string()]);
+    }
+    flags
+}
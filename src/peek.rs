@@ -0,0 +1,102 @@
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dispatch_batch, get_monitors, instance_suffix, occupancy, recent_group_workspaces, runtime_path, write_runtime_file,
+    WorkspaceMonitorMap,
+};
+
+// How long a peek lasts without --duration
+const DEFAULT_DURATION: Duration = Duration::from_secs(2);
+
+// A peek in progress, shared with the daemon through a runtime file so its
+// timer can end it
+#[derive(Serialize, Deserialize, Debug)]
+struct Peek {
+    // Unix time in milliseconds when the previous workspaces come back
+    until: u64,
+    // Workspaces shown before the peek, the focused monitor's last
+    previous: Vec<i32>,
+}
+
+fn file() -> String {
+    format!("peek{}.json", instance_suffix())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// "2s", "500ms" or plain seconds
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    if let Some(ms) = text.strip_suffix("ms") {
+        return ms.parse().ok().map(Duration::from_millis);
+    }
+    let secs: f64 = text.strip_suffix('s').unwrap_or(text).parse().ok()?;
+    (secs >= 0.0).then(|| Duration::from_secs_f64(secs))
+}
+
+// Active workspace of every monitor, the focused one last
+fn shown_workspaces() -> Vec<i32> {
+    let mut shown: Vec<(bool, i32)> = get_monitors()
+        .iter()
+        .map(|m| (m.focused, m.active_workspace.id))
+        .filter(|(_, id)| *id > 0)
+        .collect();
+    shown.sort_by_key(|(focused, _)| *focused);
+    shown.into_iter().map(|(_, id)| id).collect()
+}
+
+// Show group `workspace` on every monitor for `duration`, then go back. The
+// daemon's timer ends the peek when it runs; otherwise this waits for it.
+pub fn peek(workspace: i32, maps: &[WorkspaceMonitorMap], duration: Option<Duration>) -> Result<(), String> {
+    let targets = recent_group_workspaces(workspace, maps);
+    if targets.is_empty() {
+        return Err("No matching workspaces found".to_string());
+    }
+
+    // Peeking again while a peek is on extends it, still returning to what
+    // was shown before the first one
+    let previous = load().map(|p| p.previous).unwrap_or_else(shown_workspaces);
+    let peek = Peek {
+        until: now_ms() + duration.unwrap_or(DEFAULT_DURATION).as_millis() as u64,
+        previous,
+    };
+    let json = serde_json::to_string(&peek).map_err(|e| e.to_string())?;
+    write_runtime_file(&file(), &json).map_err(|e| format!("Couldn't record the peek: {}", e))?;
+
+    let dispatches: Vec<String> = targets.iter().map(|ws| format!("workspace {}", ws)).collect();
+    dispatch_batch(&dispatches);
+
+    if !occupancy::daemon_running() {
+        std::thread::sleep(Duration::from_millis(peek.until.saturating_sub(now_ms())));
+        end_due();
+    }
+    Ok(())
+}
+
+fn load() -> Option<Peek> {
+    serde_json::from_str(&fs::read_to_string(runtime_path(&file())).ok()?).ok()
+}
+
+// Restore the workspaces of a peek whose time is up
+fn end_due() {
+    let Some(peek) = load().filter(|p| p.until <= now_ms()) else {
+        return;
+    };
+    let _ = fs::remove_file(runtime_path(&file()));
+    let dispatches: Vec<String> = peek.previous.iter().map(|ws| format!("workspace {}", ws)).collect();
+    dispatch_batch(&dispatches);
+}
+
+// The daemon's peek timer
+pub fn watch() {
+    loop {
+        std::thread::sleep(Duration::from_millis(100));
+        end_due();
+    }
+}
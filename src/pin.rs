@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::{role, state};
+use crate::{
+    dispatch_automatic, get_active_window, get_active_window_address, get_clients, get_connected_monitor_names,
+    HyprlandClient, WorkspaceMonitorMap,
+};
+
+// Where a window pinned with `hyprws pin` belongs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Pin {
+    // Workspace group (1-10)
+    pub group: i32,
+    // Monitor whose workspace of the group it belongs on; any if unset
+    pub monitor: Option<String>,
+}
+
+// Pin the active window to a group, optionally on a specific monitor
+pub fn pin(group: i32, monitor: Option<String>) -> Result<(), String> {
+    if !(1..=10).contains(&group) {
+        return Err(format!("Invalid group: {}", group));
+    }
+    let active = get_active_window().ok_or_else(|| "No active window to pin".to_string())?;
+
+    state::update(|state| {
+        state.pins.insert(active.address.clone(), Pin { group, monitor });
+    })
+    .map_err(|e| format!("Couldn't save pin: {}", e))
+}
+
+pub fn unpin() -> Result<(), String> {
+    let address = get_active_window_address();
+    let mut removed = false;
+    state::update(|state| removed = state.pins.remove(&address).is_some())
+        .map_err(|e| format!("Couldn't save pins: {}", e))?;
+    if removed {
+        Ok(())
+    } else {
+        Err("The active window isn't pinned".to_string())
+    }
+}
+
+// Pin for a window: one made with `hyprws pin`, else the first [[pins]]
+// entry matching its class
+fn pin_for(client: &HyprlandClient, pinned: &HashMap<String, Pin>, config: &Config) -> Option<Pin> {
+    let pin = pinned.get(&client.address).cloned().or_else(|| class_pin(client, config))?;
+    // The monitor may be given as a role
+    Some(Pin {
+        monitor: pin.monitor.and_then(|m| role::resolve(&m, config)),
+        ..pin
+    })
+}
+
+fn class_pin(client: &HyprlandClient, config: &Config) -> Option<Pin> {
+    config
+        .pins
+        .iter()
+        .find(|p| match &p.matcher {
+            Some(matcher) => matcher.is_match(&client.class),
+            None => Regex::new(&format!("^(?:{})$", p.class)).is_ok_and(|re| re.is_match(&client.class)),
+        })
+        .map(|p| Pin {
+            group: p.group,
+            monitor: p.monitor.clone(),
+        })
+}
+
+// Workspace a pinned window should be on, or None if it's already in place
+fn target(client: &HyprlandClient, pin: &Pin, maps: &[WorkspaceMonitorMap], connected: &[String]) -> Option<i32> {
+    let in_group = |m: &&WorkspaceMonitorMap| m.workspace % 10 == pin.group % 10;
+    // A pin to a monitor that's gone falls back to any monitor
+    let monitor = pin.monitor.as_ref().filter(|m| connected.contains(m));
+
+    let current = maps.iter().find(|m| m.workspace == client.workspace.id);
+    let in_place = current.is_some_and(|m| in_group(&m) && monitor.is_none_or(|name| m.monitor == *name));
+    if in_place {
+        return None;
+    }
+
+    maps.iter()
+        .filter(in_group)
+        .find(|m| monitor.is_none_or(|name| m.monitor == *name))
+        .map(|m| m.workspace)
+}
+
+// Put pinned windows back in their group (and monitor) after Hyprland has
+// shuffled workspaces around, and forget pins of closed windows
+pub fn enforce(maps: &[WorkspaceMonitorMap], config: &Config) {
+    let mut pinned = state::load().pins;
+    if pinned.is_empty() && config.pins.is_empty() {
+        return;
+    }
+
+    let clients = get_clients();
+    let connected = get_connected_monitor_names();
+
+    let before = pinned.len();
+    pinned.retain(|address, _| clients.iter().any(|c| c.address == *address));
+    if pinned.len() != before {
+        let current = pinned.clone();
+        if let Err(e) = state::update(|state| state.pins = current) {
+            eprintln!("Warning: couldn't save pins: {}", e);
+        }
+    }
+
+    let dispatches: Vec<_> = clients
+        .iter()
+        .filter(|c| !c.workspace.name.starts_with("special:"))
+        .filter_map(|c| {
+            let pin = pin_for(c, &pinned, config)?;
+            let workspace = target(c, &pin, maps, &connected)?;
+            Some(format!("movetoworkspacesilent {},address:{}", workspace, c.address))
+        })
+        .collect();
+    if !dispatches.is_empty() {
+        println!("Returning {} pinned window(s) to their workspaces", dispatches.len());
+        dispatch_automatic(&dispatches, config);
+    }
+}
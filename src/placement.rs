@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::config::{Config, Placement};
+use crate::outputs::{self, Outputs};
+use crate::{state, Monitor, MonitorConfig, MAX_WORKSPACES};
+
+// Blocks of 10 workspace numbers there is room for
+const BLOCKS: usize = MAX_WORKSPACES / 10;
+
+// What a monitor's block is remembered under: the description, which
+// follows the monitor across ports, or the connector name without one
+fn key(monitor: &Monitor) -> &str {
+    if monitor.description.is_empty() {
+        &monitor.name
+    } else {
+        &monitor.description
+    }
+}
+
+// Pick a block for a new monitor from the first of `candidates` that's free,
+// preferring blocks no disconnected monitor remembers as its own
+fn first_free<I>(candidates: I, used: &[usize], claimed: &[usize]) -> Option<usize>
+where
+    I: Iterator<Item = usize> + Clone,
+{
+    let mut free = candidates.filter(|b| *b < BLOCKS && !used.contains(b));
+    free.clone().find(|b| !claimed.contains(b)).or_else(|| free.next())
+}
+
+fn append(used: &[usize], claimed: &[usize], reserved: &[usize]) -> usize {
+    let next = used.iter().max().map_or(0, |b| b + 1);
+    let open = (next..BLOCKS).chain(0..next).filter(|b| !reserved.contains(b));
+    first_free(open, used, claimed)
+        .or_else(|| first_free(reserved.iter().copied(), used, claimed))
+        // Out of room; building the rules reports it
+        .unwrap_or(BLOCKS + used.len())
+}
+
+// Workspace block index for each enabled monitor, as (block, name) in block
+// order, with [[outputs]] applied. Monitors keep the block remembered for
+// them; new ones are placed by config.placement, and remembered once rules
+// for them are written.
+pub fn place(monitor_config: &MonitorConfig, config: &Config) -> Result<(Vec<(usize, String)>, Outputs), String> {
+    let mut monitors: Vec<&Monitor> = monitor_config.enabled().collect();
+    monitors.sort_by_key(|m| m.id);
+
+    let remembered = state::load().blocks;
+    let reserved = config.reserved_block_indexes();
+
+    let mut placed: Vec<(usize, &Monitor)> = Vec::new();
+    let mut new = Vec::new();
+    for monitor in monitors {
+        match remembered.get(key(monitor)) {
+            // Two monitors sharing a description can't share the block
+            Some(block) if !placed.iter().any(|(b, _)| b == block) => placed.push((*block, monitor)),
+            _ => new.push(monitor),
+        }
+    }
+    let connected: Vec<&str> = placed.iter().map(|(_, m)| key(m)).chain(new.iter().map(|m| key(m))).collect();
+    let claimed: Vec<usize> = remembered
+        .iter()
+        .filter(|(k, _)| !connected.contains(&k.as_str()))
+        .map(|(_, b)| *b)
+        .collect();
+
+    for monitor in new {
+        let used: Vec<usize> = placed.iter().map(|(b, _)| *b).collect();
+        match config.placement {
+            Placement::Append => placed.push((append(&used, &claimed, &reserved), monitor)),
+            Placement::Reserved => {
+                let block = first_free(reserved.iter().copied(), &used, &claimed)
+                    .unwrap_or_else(|| append(&used, &claimed, &reserved));
+                placed.push((block, monitor));
+            }
+            Placement::Position => {
+                placed.sort_by_key(|(b, _)| *b);
+                let index = placed.iter().filter(|(_, m)| (m.x, m.y) < (monitor.x, monitor.y)).count();
+                placed.insert(index, (usize::MAX, monitor));
+                // Keep each block unless the one before has caught up with it
+                let mut next = 0;
+                for (block, _) in placed.iter_mut() {
+                    if *block == usize::MAX || *block < next {
+                        while reserved.contains(&next) {
+                            next += 1;
+                        }
+                        *block = next;
+                    }
+                    next = *block + 1;
+                }
+            }
+        }
+    }
+    placed.sort_by_key(|(b, _)| *b);
+    let placed: Vec<(usize, String)> = placed.into_iter().map(|(b, m)| (b, m.name.clone())).collect();
+    outputs::apply(&placed, config)
+}
+
+// Remember the blocks of placed monitors for the next placement; connected
+// monitors left without one (mirrors) are forgotten
+pub fn remember(monitor_config: &MonitorConfig, placed: &[(usize, String)]) {
+    let mut blocks: HashMap<String, Option<usize>> = HashMap::new();
+    for monitor in monitor_config.enabled() {
+        let block = placed.iter().find(|(b, name)| *name == monitor.name && *b < BLOCKS).map(|(b, _)| *b);
+        blocks.insert(key(monitor).to_string(), block);
+    }
+    let remembered = state::load().blocks;
+    if blocks.iter().all(|(k, b)| remembered.get(k).copied() == *b) {
+        return;
+    }
+    let result = state::update(|state| {
+        for (key, block) in blocks {
+            match block {
+                Some(block) => state.blocks.insert(key, block),
+                None => state.blocks.remove(&key),
+            };
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: couldn't remember workspace blocks: {}", e);
+    }
+}
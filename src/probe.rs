@@ -0,0 +1,91 @@
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::ipc;
+
+// socket2 events and the first Hyprland release (major, minor) emitting
+// them; (0, 0) for events every supported release has
+const EVENTS: &[(&str, (u32, u32))] = &[
+    ("workspace", (0, 0)),
+    ("focusedmon", (0, 0)),
+    ("activewindow", (0, 0)),
+    ("fullscreen", (0, 0)),
+    ("monitorremoved", (0, 0)),
+    ("monitoradded", (0, 0)),
+    ("createworkspace", (0, 0)),
+    ("destroyworkspace", (0, 0)),
+    ("moveworkspace", (0, 0)),
+    ("activelayout", (0, 0)),
+    ("openwindow", (0, 0)),
+    ("closewindow", (0, 0)),
+    ("movewindow", (0, 0)),
+    ("openlayer", (0, 0)),
+    ("closelayer", (0, 0)),
+    ("submap", (0, 0)),
+    ("activewindowv2", (0, 23)),
+    ("changefloatingmode", (0, 24)),
+    ("urgent", (0, 25)),
+    ("minimize", (0, 26)),
+    ("windowtitle", (0, 27)),
+    ("renameworkspace", (0, 27)),
+    ("screencast", (0, 28)),
+    ("activespecial", (0, 29)),
+    ("togglegroup", (0, 30)),
+    ("moveintogroup", (0, 30)),
+    ("moveoutofgroup", (0, 30)),
+    ("ignoregrouplock", (0, 31)),
+    ("lockgroups", (0, 31)),
+    ("configreloaded", (0, 31)),
+    ("pin", (0, 32)),
+    ("workspacev2", (0, 34)),
+    ("createworkspacev2", (0, 34)),
+    ("destroyworkspacev2", (0, 34)),
+    ("moveworkspacev2", (0, 34)),
+    ("movewindowv2", (0, 34)),
+    ("monitoraddedv2", (0, 35)),
+    ("monitorremovedv2", (0, 35)),
+    ("windowtitlev2", (0, 42)),
+    ("bell", (0, 45)),
+    ("focusedmonv2", (0, 46)),
+    ("activespecialv2", (0, 48)),
+];
+
+// Subset of the version -j response
+#[derive(Deserialize, Debug)]
+struct Version {
+    // "v0.41.2", with a suffix on builds between releases
+    #[serde(default)]
+    tag: String,
+}
+
+// (major, minor) of the running Hyprland, None when it can't be told
+fn hyprland_version() -> Option<(u32, u32)> {
+    let version: Version = ipc::query("version")?;
+    let mut parts = version.tag.trim_start_matches('v').split(['.', '-']);
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+// Warn about hooks on events this Hyprland never emits, which would
+// otherwise sit in the config doing nothing
+pub fn check_hooks(config: &Config) {
+    if config.hooks.is_empty() {
+        return;
+    }
+    let version = hyprland_version();
+    let mut events: Vec<&String> = config.hooks.keys().collect();
+    events.sort();
+
+    for event in events {
+        match EVENTS.iter().find(|(name, _)| name == event) {
+            None => eprintln!("Warning: [hooks.{}] will never run, Hyprland has no '{}' event", event, event),
+            Some((_, (major, minor))) => {
+                if let Some(running) = version.filter(|v| *v < (*major, *minor)) {
+                    eprintln!(
+                        "Warning: [hooks.{}] will never run, '{}' needs Hyprland {}.{} or newer (running {}.{})",
+                        event, event, major, minor, running.0, running.1
+                    );
+                }
+            }
+        }
+    }
+}
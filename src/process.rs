@@ -0,0 +1,49 @@
+use std::ffi::OsStr;
+use std::io;
+use std::process::Command;
+
+use crate::context;
+
+// A command for hyprctl, hooks and scripts, with the selected Hyprland
+// instance in its environment
+pub fn command<S: AsRef<OsStr>>(program: S) -> Command {
+    let mut command = Command::new(program);
+    if let Some(signature) = context::instance() {
+        command.env("HYPRLAND_INSTANCE_SIGNATURE", signature);
+    }
+    command
+}
+
+// Run a program to completion, returning its stdout; failures are reported
+pub fn output(program: &str, args: &[&str]) -> Option<String> {
+    match command(program).args(args).output() {
+        Ok(output) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Ok(output) => {
+            eprintln!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+        Err(e) => {
+            eprintln!("Failed to run {}: {}", program, e);
+            None
+        }
+    }
+}
+
+// Run a program to completion, reporting failures
+pub fn run(program: &str, args: &[&str]) {
+    output(program, args);
+}
+
+// Start a command without waiting on it; it's reaped in the background so
+// it doesn't linger as a zombie. Returns its PID.
+pub fn spawn(command: &mut Command) -> io::Result<u32> {
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    std::thread::spawn(move || child.wait());
+    Ok(pid)
+}
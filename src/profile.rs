@@ -0,0 +1,103 @@
+use std::io;
+use std::process::Command;
+
+use crate::config::{Config, Profile};
+use crate::{night_light, process, role, state, wallpaper};
+use crate::{assign_layout, assign_workspaces, ipc};
+
+// Name of the last applied profile, if any
+pub fn get_active_profile() -> Option<String> {
+    state::load().profile
+}
+
+fn set_active_profile(name: &str) -> io::Result<()> {
+    state::update(|state| state.profile = Some(name.to_string()))
+}
+
+// Find the profile whose monitor list matches the connected monitors
+pub fn match_profile<'a>(config: &'a Config, connected: &[String]) -> Option<&'a str> {
+    config
+        .profiles
+        .iter()
+        .find(|(_, profile)| {
+            // Roles resolve only when their monitor is connected
+            let Some(monitors) = role::resolve_all(&profile.monitors, config) else {
+                return false;
+            };
+            !monitors.is_empty()
+                && monitors.len() == connected.len()
+                && monitors.iter().all(|m| connected.contains(m))
+        })
+        .map(|(name, _)| name.as_str())
+}
+
+// Run profile hook commands through the shell without waiting on them
+fn run_hooks(hooks: &[String], profile_name: &str) {
+    for hook in hooks {
+        let mut command = process::command("sh");
+        command.arg("-c").arg(hook).env("HYPRWS_PROFILE", profile_name);
+        if let Err(e) = process::spawn(&mut command) {
+            eprintln!("Failed to run hook '{}': {}", hook, e);
+        }
+    }
+}
+
+// Built-in hook: switch the system power profile
+fn apply_power_profile(power_profile: &str) {
+    match Command::new("powerprofilesctl").args(["set", power_profile]).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("powerprofilesctl set {} exited with {}", power_profile, status),
+        Err(e) => eprintln!("Failed to run powerprofilesctl: {}", e),
+    }
+}
+
+// Apply a named profile: monitor keywords, workspace mapping, then hooks
+pub fn apply_profile(name: &str, config: &Config, ws_path: &str) -> Result<(), String> {
+    let profile: &Profile = config
+        .profiles
+        .get(name)
+        .ok_or_else(|| format!("Unknown profile '{}'", name))?;
+
+    // Leave the previous profile first
+    if let Some(previous) = get_active_profile().filter(|p| p != name) {
+        if let Some(previous_profile) = config.profiles.get(&previous) {
+            run_hooks(&previous_profile.on_exit, &previous);
+        }
+    }
+
+    for keyword in &profile.monitor_keywords {
+        if let Some(keyword) = role::resolve_keyword(keyword, config) {
+            ipc::request(&format!("keyword monitor {}", keyword));
+        }
+    }
+
+    let assigned = if profile.monitors.is_empty() {
+        assign_workspaces(ws_path, Some(name), config)
+    } else {
+        let monitors = role::resolve_all(&profile.monitors, config)
+            .ok_or_else(|| format!("A monitor role in profile '{}' isn't connected", name))?;
+        assign_layout(ws_path, Some(name), &monitors, config)
+    };
+    if assigned.is_none() {
+        return Err(format!("Failed to assign workspaces for profile '{}'", name));
+    }
+
+    if let Some(backend) = config.wallpaper_backend.filter(|_| !profile.wallpapers.is_empty()) {
+        wallpaper::apply(&profile.wallpapers, backend, config);
+    }
+
+    if let Some(backend) = config.night_light_backend.filter(|_| !profile.night_light.is_empty()) {
+        night_light::apply(&profile.night_light, backend, config);
+    }
+
+    if let Err(e) = set_active_profile(name) {
+        eprintln!("Warning: couldn't record active profile: {}", e);
+    }
+
+    if let Some(power_profile) = &profile.power_profile {
+        apply_power_profile(power_profile);
+    }
+
+    run_hooks(&profile.on_enter, name);
+    Ok(())
+}
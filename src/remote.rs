@@ -0,0 +1,175 @@
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::config::Remote;
+use crate::daemon::Shared;
+use crate::{context, paths};
+
+// Commands a remote caller may run: workspace and profile changes only
+const ALLOWED_COMMANDS: [&str; 10] = [
+    "-s", "--workspace", "-m", "--move", "profile", "peek", "rotate", "goto", "send-workspace", "move",
+];
+
+// Options the CLI takes anywhere on the command line; the daemon's own are
+// passed on instead
+const GLOBAL_FLAGS: [&str; 6] = ["--instance", "--config", "--cache-dir", "--read-only", "-y", "--yes"];
+
+// Longest a client may take to send its request or read the reply
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Largest request read, headers included
+const MAX_REQUEST: u64 = 16 * 1024;
+
+// Connections handled at once; more are closed right away
+const MAX_CONNECTIONS: usize = 8;
+
+/// Connections being handled
+static OPEN: AtomicUsize = AtomicUsize::new(0);
+
+// Compare tokens without returning early on the first mismatch
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+// Answer `state` from the daemon's session, run anything else through hyprws
+fn run(args: &[String], shared: &Shared) -> (bool, String) {
+    if args.len() == 1 && args[0] == "state" {
+        return (true, format!("{}\n", shared.state()));
+    }
+    run_hyprws(args)
+}
+
+// Run hyprws with the given arguments, returning (success, output)
+fn run_hyprws(args: &[String]) -> (bool, String) {
+    if args.is_empty() || !ALLOWED_COMMANDS.contains(&args[0].as_str()) {
+        return (false, "Command not allowed remotely\n".to_string());
+    }
+    if let Some(flag) = args.iter().find(|a| GLOBAL_FLAGS.contains(&a.as_str())) {
+        return (false, format!("{} not allowed remotely\n", flag));
+    }
+
+    let mut command = match env::current_exe() {
+        Ok(exe) => Command::new(exe),
+        Err(e) => return (false, format!("{}\n", e)),
+    };
+    if let Some(instance) = context::instance() {
+        command.args(["--instance", instance]);
+    }
+    command.args(paths::flags());
+    // Remote callers can't answer prompts; without a terminal they proceed
+    match command.args(args).stdin(Stdio::null()).output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.success(), text)
+        }
+        Err(e) => (false, format!("{}\n", e)),
+    }
+}
+
+// Handle one connection. Two request forms are accepted:
+//   plain TCP:  "TOKEN ARGS...\n", answered with the command output
+//   HTTP:       POST with "Authorization: Bearer TOKEN" and the arguments as body
+fn handle(stream: TcpStream, token: &str, shared: &Shared) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST));
+    let mut writer = stream;
+
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+
+    if first_line.starts_with("POST ") {
+        let mut authorized = false;
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                match name.trim().to_lowercase().as_str() {
+                    "authorization" => {
+                        let given = value.trim().strip_prefix("Bearer ").unwrap_or_default();
+                        authorized = token_matches(given, token);
+                    }
+                    "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+        }
+
+        let (status, body) = if authorized {
+            let mut body = vec![0; content_length.min(4096)];
+            reader.read_exact(&mut body)?;
+            let args: Vec<String> = String::from_utf8_lossy(&body)
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect();
+            match run(&args, shared) {
+                (true, output) => ("200 OK", output),
+                (false, output) => ("500 Internal Server Error", output),
+            }
+        } else {
+            ("401 Unauthorized", "Invalid token\n".to_string())
+        };
+        write!(
+            writer,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        return Ok(());
+    }
+
+    let mut words = first_line.split_whitespace();
+    if !words.next().is_some_and(|given| token_matches(given, token)) {
+        writer.write_all(b"ERR invalid token\n")?;
+        return Ok(());
+    }
+    let args: Vec<String> = words.map(|s| s.to_string()).collect();
+    let (success, output) = run(&args, shared);
+    writer.write_all(output.as_bytes())?;
+    writer.write_all(if success { b"OK\n" } else { b"ERR\n" })
+}
+
+// Accept remote commands on a loopback TCP port, for stream decks and
+// phone shortcuts (reach it from other devices through SSH forwarding).
+// `state` answers with the daemon's view of the session as JSON.
+pub fn serve(remote: &Remote, shared: &Shared) {
+    let Some(token) = remote.token.as_deref().filter(|t| !t.is_empty()) else {
+        eprintln!("Remote control needs [remote] token to be set, not starting it");
+        return;
+    };
+
+    let listener = match TcpListener::bind(("127.0.0.1", remote.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Couldn't bind remote control port {}: {}", remote.port, e);
+            return;
+        }
+    };
+    println!("Remote control listening on 127.0.0.1:{}", remote.port);
+
+    std::thread::scope(|scope| {
+        for stream in listener.incoming().flatten() {
+            if OPEN.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                OPEN.fetch_sub(1, Ordering::SeqCst);
+                eprintln!("Too many remote control connections, dropping one");
+                continue;
+            }
+            scope.spawn(move || {
+                if let Err(e) = handle(stream, token, shared) {
+                    eprintln!("Remote control connection failed: {}", e);
+                }
+                OPEN.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+}
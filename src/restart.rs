@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::{check, process};
+
+// How long a restarted unit or command gets before it's checked on
+const HEALTH_DELAY: Duration = Duration::from_secs(2);
+
+// Restart restart_units and run restart_command once reassignments have
+// been quiet for restart_debounce, so a hotplug storm restarts bars once.
+// Reassignments that left the generated rules as they were restart nothing.
+pub fn watch(receiver: mpsc::Receiver<()>, config: &Config, path: &str) {
+    let debounce = Duration::from_millis(config.restart_debounce());
+    let mut last = generated(path);
+    while receiver.recv().is_ok() {
+        while receiver.recv_timeout(debounce).is_ok() {}
+
+        let current = generated(path);
+        if current == last {
+            continue;
+        }
+        last = current;
+
+        for unit in &config.restart_units {
+            restart_unit(unit);
+        }
+        if let Some(command) = &config.restart_command {
+            run_restart_command(command);
+        }
+    }
+}
+
+// The generated rules file with the files it sources (a profile's layout
+// with profile_outputs) inlined
+fn generated(path: &str) -> String {
+    let mut contents = String::new();
+    collect(path, &mut Vec::new(), &mut contents);
+    contents
+}
+
+fn collect(path: &str, visited: &mut Vec<String>, contents: &mut String) {
+    // Compared canonically, so layouts/../ws.conf is ws.conf
+    let canonical = fs::canonicalize(path).map_or(path.to_string(), |p| p.to_string_lossy().into_owned());
+    if visited.contains(&canonical) {
+        return;
+    }
+    visited.push(canonical);
+    for line in fs::read_to_string(path).unwrap_or_default().lines() {
+        let uncommented = check::strip_comment(line);
+        let source = uncommented.trim().strip_prefix("source").and_then(|rest| rest.trim_start().strip_prefix('='));
+        match source {
+            Some(source) => collect(&check::expand(source.trim(), Path::new(path)), visited, contents),
+            None => {
+                contents.push_str(line);
+                contents.push('\n');
+            }
+        }
+    }
+}
+
+fn is_active(unit: &str) -> bool {
+    Command::new("systemctl")
+        .args(["--user", "is-active", "--quiet", unit])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+// Restart a user unit, trying once more if it isn't running afterwards
+fn restart_unit(unit: &str) {
+    for attempt in 1..=2 {
+        println!("Restarting {}", unit);
+        if let Err(e) = Command::new("systemctl").args(["--user", "restart", unit]).status() {
+            eprintln!("Failed to run systemctl: {}", e);
+            return;
+        }
+        thread::sleep(HEALTH_DELAY);
+        if is_active(unit) {
+            return;
+        }
+        eprintln!("Warning: {} isn't running after restart (attempt {})", unit, attempt);
+    }
+}
+
+// Run the restart command, reporting it if it dies right away
+fn run_restart_command(command: &str) {
+    let mut child = match process::command("sh").arg("-c").arg(command).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to run '{}': {}", command, e);
+            return;
+        }
+    };
+    thread::sleep(HEALTH_DELAY);
+    match child.try_wait() {
+        Ok(Some(status)) if !status.success() => eprintln!("Warning: '{}' exited with {}", command, status),
+        Ok(Some(_)) => {}
+        // Still running (e.g. a bar started in the foreground); reap it later
+        _ => {
+            thread::spawn(move || child.wait());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_rules_include_sourced_files() {
+        let dir = std::env::temp_dir().join(format!("hyprws-restart-{}", std::process::id()));
+        fs::create_dir_all(dir.join("layouts")).unwrap();
+        let ws_conf = dir.join("ws.conf");
+        fs::write(&ws_conf, "source = layouts/a.conf\n").unwrap();
+        fs::write(dir.join("layouts/a.conf"), "workspace = 1, monitor:DP-1\nsource = ../ws.conf\n").unwrap();
+        let before = generated(&ws_conf.to_string_lossy());
+
+        fs::write(dir.join("layouts/a.conf"), "workspace = 1, monitor:DP-2\nsource = ../ws.conf\n").unwrap();
+        let after = generated(&ws_conf.to_string_lossy());
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(before, "workspace = 1, monitor:DP-1\n");
+        assert_eq!(after, "workspace = 1, monitor:DP-2\n");
+    }
+}
@@ -0,0 +1,72 @@
+use crate::config::Config;
+use crate::{ipc, HyprlandMonitor};
+
+// Connector names and descriptions of every monitor Hyprland knows about
+fn monitor_descriptions() -> Vec<(String, String)> {
+    let monitors: Vec<HyprlandMonitor> = ipc::query("monitors all").unwrap_or_default();
+    monitors.into_iter().map(|m| (m.name, m.description)).collect()
+}
+
+// Connector of the monitor a role from [roles] describes, if it's connected
+fn connector_for(role: &str, config: &Config) -> Option<String> {
+    let description = config.roles.get(role)?;
+    monitor_descriptions()
+        .into_iter()
+        .find(|(_, d)| d.contains(description.as_str()))
+        .map(|(name, _)| name)
+}
+
+// Resolve a monitor given by role or connector name to a connector name.
+// Names that aren't roles pass through unchanged.
+pub fn resolve(name: &str, config: &Config) -> Option<String> {
+    if config.roles.contains_key(name) {
+        connector_for(name, config)
+    } else {
+        Some(name.to_string())
+    }
+}
+
+// Resolve a list of monitors, None if any role isn't connected
+pub fn resolve_all(names: &[String], config: &Config) -> Option<Vec<String>> {
+    if !names.iter().any(|n| config.roles.contains_key(n)) {
+        return Some(names.to_vec());
+    }
+    // One query for the whole list
+    let descriptions = monitor_descriptions();
+    names
+        .iter()
+        .map(|name| match config.roles.get(name) {
+            Some(description) => descriptions
+                .iter()
+                .find(|(_, d)| d.contains(description.as_str()))
+                .map(|(connector, _)| connector.clone()),
+            None => Some(name.clone()),
+        })
+        .collect()
+}
+
+// Resolve a monitor for a setting sent to Hyprland or another tool. A role
+// that isn't connected is reported, and the caller skips the setting rather
+// than pass the role's name on as a connector.
+pub fn resolve_or_warn(name: &str, config: &Config) -> Option<String> {
+    let connector = resolve(name, config);
+    if connector.is_none() {
+        eprintln!("Warning: no connected monitor has role '{}', skipping its setting", name);
+    }
+    connector
+}
+
+// Replace a role in the NAME field of a `hyprctl keyword monitor` value;
+// None when the role isn't connected
+pub fn resolve_keyword(keyword: &str, config: &Config) -> Option<String> {
+    let (name, rest) = keyword.split_once(',').unwrap_or((keyword, ""));
+    if !config.roles.contains_key(name.trim()) {
+        return Some(keyword.to_string());
+    }
+    let connector = resolve_or_warn(name.trim(), config)?;
+    if rest.is_empty() {
+        Some(connector)
+    } else {
+        Some(format!("{},{}", connector, rest))
+    }
+}
@@ -0,0 +1,42 @@
+use std::io;
+use std::mem::MaybeUninit;
+
+// Signals that stop the daemon: systemctl stop and Ctrl-C
+const SIGNALS: [libc::c_int; 2] = [libc::SIGTERM, libc::SIGINT];
+
+fn signal_set() -> libc::sigset_t {
+    let mut set = MaybeUninit::uninit();
+    // SAFETY: sigemptyset initializes the set before sigaddset reads it
+    unsafe {
+        libc::sigemptyset(set.as_mut_ptr());
+        for signal in SIGNALS {
+            libc::sigaddset(set.as_mut_ptr(), signal);
+        }
+        set.assume_init()
+    }
+}
+
+// Hold the stop signals for wait() in this thread and every thread it starts
+// from now on; call before starting any
+pub fn block() -> io::Result<()> {
+    let set = signal_set();
+    // SAFETY: set is initialized and the old mask isn't asked for
+    match unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) } {
+        0 => Ok(()),
+        e => Err(io::Error::from_raw_os_error(e)),
+    }
+}
+
+// Wait for a stop signal, clean up and exit. Should waiting fail, the
+// daemon stops all the same rather than ignore the signals it blocked.
+pub fn wait<F: FnOnce()>(cleanup: F) -> ! {
+    let set = signal_set();
+    let mut signal = 0;
+    // SAFETY: set is initialized and signal outlives the call
+    match unsafe { libc::sigwait(&set, &mut signal) } {
+        0 => println!("Stopping on signal {}", signal),
+        e => eprintln!("Couldn't wait for signals, stopping: {}", io::Error::from_raw_os_error(e)),
+    }
+    cleanup();
+    std::process::exit(0);
+}
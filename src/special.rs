@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::{dispatch_batch, get_monitors, instance_suffix, runtime_path, write_runtime_file};
+
+fn file() -> String {
+    format!("specials{}.json", instance_suffix())
+}
+
+// Name for togglespecialworkspace: "special:term" -> "term"
+fn short_name(special: &str) -> &str {
+    special.strip_prefix("special:").unwrap_or(special)
+}
+
+// Special workspace shown on each monitor, straight from Hyprland
+pub fn query() -> HashMap<String, String> {
+    get_monitors()
+        .into_iter()
+        .filter(|m| !m.special_workspace.name.is_empty())
+        .map(|m| (m.name, m.special_workspace.name))
+        .collect()
+}
+
+// Special workspace shown on each monitor, as last recorded by the daemon or
+// a toggle, queried when nothing has been recorded
+pub fn visible() -> HashMap<String, String> {
+    std::fs::read_to_string(runtime_path(&file()))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_else(query)
+}
+
+pub fn save(specials: &HashMap<String, String>) {
+    let json = serde_json::to_string(specials).unwrap_or_default();
+    if let Err(e) = write_runtime_file(&file(), &json) {
+        eprintln!("Warning: couldn't record special workspaces: {}", e);
+    }
+}
+
+// Track activespecial>>NAME,MONITOR; NAME is empty once the monitor hides it
+pub fn apply(specials: &mut HashMap<String, String>, data: &str) {
+    let Some((name, monitor)) = data.rsplit_once(',') else {
+        return;
+    };
+    if name.is_empty() {
+        specials.remove(monitor);
+    } else {
+        specials.insert(monitor.to_string(), name.to_string());
+    }
+}
+
+// togglespecialworkspace, then record what's visible so status and bars
+// don't wait for the daemon
+pub fn toggle(name: Option<&str>) {
+    let dispatch = match name {
+        Some(name) => format!("togglespecialworkspace {}", short_name(name)),
+        None => "togglespecialworkspace".to_string(),
+    };
+    dispatch_batch(&[dispatch]);
+    save(&query());
+}
+
+// Dispatches closing every special workspace shown right now, each from
+// its own monitor. Focus ends on the last of them, so callers follow up with
+// a workspace switch.
+pub fn hide_dispatches() -> Vec<String> {
+    query()
+        .iter()
+        .flat_map(|(monitor, special)| {
+            [
+                format!("focusmonitor {}", monitor),
+                format!("togglespecialworkspace {}", short_name(special)),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::state;
+use crate::{dispatch_batch, get_active_window, get_clients};
+
+// A window hidden by `hyprws stash`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StashEntry {
+    address: String,
+    // Workspace the window is restored to
+    workspace: i32,
+    class: String,
+    title: String,
+}
+
+// Stashed windows, most recent last
+fn load_stack() -> Vec<StashEntry> {
+    state::load().stash
+}
+
+fn save_stack(stack: &[StashEntry]) -> io::Result<()> {
+    state::update(|state| state.stash = stack.to_vec())
+}
+
+// Move the active window to its monitor's hidden stash workspace
+pub fn stash() -> Result<(), String> {
+    let active = get_active_window().ok_or_else(|| "No active window to stash".to_string())?;
+    if active.workspace.name.starts_with("special:stash") {
+        return Err("The active window is already stashed".to_string());
+    }
+
+    let mut stack = load_stack();
+    stack.push(StashEntry {
+        address: active.address.clone(),
+        workspace: active.workspace.id,
+        class: active.class,
+        title: active.title,
+    });
+    save_stack(&stack).map_err(|e| format!("Couldn't save stash: {}", e))?;
+
+    dispatch_batch(&[format!(
+        "movetoworkspacesilent special:stash-{},address:{}",
+        active.monitor, active.address
+    )]);
+    Ok(())
+}
+
+// Ask the configured picker to choose one of the stashed windows
+fn pick(stack: &[StashEntry], config: &Config) -> Option<usize> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(config.picker())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Most recent first
+        for (index, entry) in stack.iter().enumerate().rev() {
+            let _ = writeln!(stdin, "{}: {} ({})", index, entry.title, entry.class);
+        }
+    }
+
+    let output = child.wait_with_output().ok()?;
+    let choice = String::from_utf8_lossy(&output.stdout);
+    choice.split(':').next()?.trim().parse().ok()
+}
+
+// Restore the most recently stashed window, or one chosen with the picker,
+// to the workspace it was stashed from
+pub fn unstash(choose: bool, config: &Config) -> Result<(), String> {
+    // Drop windows that were closed while stashed
+    let clients = get_clients();
+    let mut stack = load_stack();
+    stack.retain(|entry| clients.iter().any(|c| c.address == entry.address));
+
+    if stack.is_empty() {
+        let _ = save_stack(&stack);
+        return Err("No stashed windows".to_string());
+    }
+
+    let index = if choose {
+        pick(&stack, config).filter(|i| *i < stack.len()).ok_or("Nothing picked")?
+    } else {
+        stack.len() - 1
+    };
+
+    let entry = stack.remove(index);
+    save_stack(&stack).map_err(|e| format!("Couldn't save stash: {}", e))?;
+
+    dispatch_batch(&[
+        format!("movetoworkspace {},address:{}", entry.workspace, entry.address),
+        format!("focuswindow address:{}", entry.address),
+    ]);
+    Ok(())
+}
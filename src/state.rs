@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{self, BufReader};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{lock, paths};
+use crate::pin::Pin;
+use crate::stash::StashEntry;
+
+/// Most workspaces remembered per monitor
+const HISTORY_LENGTH: usize = 20;
+
+// Behavioral state kept across daemon restarts, stored in
+// $XDG_STATE_HOME/hyprws/state.json
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct State {
+    // Last applied profile
+    pub profile: Option<String>,
+    // Windows hidden with `hyprws stash`, most recent last
+    pub stash: Vec<StashEntry>,
+    // Recently focused workspaces per monitor, most recent last
+    pub workspace_history: HashMap<String, Vec<i32>>,
+    // Last focused window address per monitor
+    pub focus: HashMap<String, String>,
+    // Primary monitor chosen with `hyprws set-primary`, overriding the config
+    pub primary: Option<String>,
+    // Windows pinned with `hyprws pin`, keyed by address
+    pub pins: HashMap<String, Pin>,
+    // Seconds each workspace was focused, per hour of day (local time)
+    pub usage: HashMap<String, Vec<u64>>,
+    // Workspace block index per monitor description, see placement
+    pub blocks: HashMap<String, usize>,
+}
+
+impl State {
+    // Record that a workspace was focused on a monitor
+    pub fn push_workspace(&mut self, monitor: &str, workspace: i32) {
+        let history = self.workspace_history.entry(monitor.to_string()).or_default();
+        history.retain(|ws| *ws != workspace);
+        history.push(workspace);
+        if history.len() > HISTORY_LENGTH {
+            history.remove(0);
+        }
+    }
+}
+
+fn state_path() -> String {
+    format!("{}/state{}.json", paths::state_dir(), crate::instance_suffix())
+}
+
+// Load the saved state, starting fresh when there is none or it is unreadable
+pub fn load() -> State {
+    let Ok(file) = File::open(state_path()) else {
+        return State::default();
+    };
+    serde_json::from_reader(BufReader::new(file)).unwrap_or_else(|e| {
+        eprintln!("Warning: ignoring unreadable state file: {}", e);
+        State::default()
+    })
+}
+
+// Write the state atomically so a crash mid-write can't corrupt it; the
+// caller holds the state lock
+fn save(state: &State) -> io::Result<()> {
+    let json = serde_json::to_vec_pretty(state).map_err(io::Error::other)?;
+    lock::write_locked(&state_path(), &json)
+}
+
+// Read-modify-write the saved state, holding the lock throughout so
+// concurrent invocations don't drop each other's changes
+pub fn update<F: FnOnce(&mut State)>(f: F) -> io::Result<()> {
+    create_dir_all(paths::state_dir())?;
+    let _lock = lock::exclusive(&state_path())?;
+    let mut state = load();
+    f(&mut state);
+    save(&state)
+}
+
+/// How often the daemon writes its state to disk when it has changed
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+// State held in memory by the daemon and checkpointed periodically
+pub struct Tracker {
+    state: Mutex<State>,
+    dirty: AtomicBool,
+}
+
+impl Tracker {
+    // Start from the last checkpoint
+    pub fn restore() -> Self {
+        Tracker {
+            state: Mutex::new(load()),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    // Modify the in-memory state
+    pub fn with<F: FnOnce(&mut State)>(&self, f: F) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> State {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    // Write the daemon-owned fields whenever they changed
+    pub fn checkpoint_loop(&self) {
+        loop {
+            std::thread::sleep(CHECKPOINT_INTERVAL);
+            self.checkpoint();
+        }
+    }
+
+    // Write the daemon-owned fields if they changed. Profile and stash are
+    // written directly by the CLI commands, so they are left alone here.
+    pub fn checkpoint(&self) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        let snapshot = self.snapshot();
+        let result = update(|state| {
+            state.workspace_history = snapshot.workspace_history;
+            state.focus = snapshot.focus;
+            state.usage = snapshot.usage;
+        });
+        if let Err(e) = result {
+            eprintln!("Warning: couldn't checkpoint daemon state: {}", e);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::config::Config;
+use crate::{count_windows, get_clients, get_current_monitor_name, get_current_workspace, state};
+use crate::WorkspaceMonitorMap;
+
+// A workspace argument as written on the command line
+#[derive(Debug, PartialEq)]
+pub enum Token {
+    // A group number, e.g. "3"
+    Group(i32),
+    // Groups before or after the current one, e.g. "+1" or "-2"
+    Relative(i32),
+    // The previously focused workspace on the current monitor, "~"
+    Last,
+    // The first group whose workspace on the current monitor has no windows
+    FirstEmpty,
+    // Workspace n of the current monitor's block, e.g. "m:2"
+    OnMonitor(i32),
+}
+
+// What a token resolves to: a workspace number, and the monitor it must
+// stay on when the token picked one
+#[derive(Debug)]
+pub struct Target {
+    pub workspace: i32,
+    pub monitor: Option<String>,
+}
+
+pub fn parse(arg: &str) -> Result<Token, String> {
+    let invalid = || format!("Invalid workspace: {}", arg);
+    match arg {
+        "~" => Ok(Token::Last),
+        "first-empty" => Ok(Token::FirstEmpty),
+        _ if arg.starts_with('+') || arg.starts_with('-') => {
+            arg.parse().map(Token::Relative).map_err(|_| invalid())
+        }
+        _ => match arg.strip_prefix("m:") {
+            Some(n) => n.parse().map(Token::OnMonitor).map_err(|_| invalid()),
+            None => arg.parse().map(Token::Group).map_err(|_| invalid()),
+        },
+    }
+}
+
+// Group (1-10) of a workspace number
+fn group_of(workspace: i32) -> i32 {
+    (workspace - 1).rem_euclid(10) + 1
+}
+
+// Resolve a workspace argument against the current focus and windows
+pub fn resolve(arg: &str, maps: &[WorkspaceMonitorMap], config: &Config) -> Result<Target, String> {
+    let groups = config.workspaces_per_monitor() as i32;
+    let on_current = |workspace: i32| Target {
+        workspace,
+        monitor: None,
+    };
+
+    match parse(arg)? {
+        Token::Group(group) => Ok(on_current(group)),
+        Token::Relative(offset) => {
+            let current = get_current_workspace();
+            if current <= 0 {
+                return Err("The current workspace isn't numbered".to_string());
+            }
+            Ok(on_current((group_of(current) - 1 + offset).rem_euclid(groups) + 1))
+        }
+        Token::Last => {
+            let monitor = get_current_monitor_name();
+            let current = get_current_workspace();
+            state::load()
+                .workspace_history
+                .get(&monitor)
+                .and_then(|history| history.iter().rev().find(|ws| **ws != current && **ws > 0))
+                .map(|ws| on_current(*ws))
+                .ok_or_else(|| format!("No previous workspace on {}", monitor))
+        }
+        Token::FirstEmpty => {
+            let monitor = get_current_monitor_name();
+            let clients = get_clients();
+            let mut blocks: Vec<_> = maps.iter().filter(|m| m.monitor == monitor).collect();
+            blocks.sort_by_key(|m| group_of(m.workspace));
+            blocks
+                .iter()
+                .find(|m| count_windows(&clients, m.workspace, config) == 0)
+                .map(|m| on_current(m.workspace))
+                .ok_or_else(|| format!("Every workspace on {} has windows", monitor))
+        }
+        Token::OnMonitor(n) => {
+            if !(1..=groups).contains(&n) {
+                return Err(format!("Workspace m:{} is outside 1-{}", n, groups));
+            }
+            let monitor = get_current_monitor_name();
+            maps.iter()
+                .find(|m| m.monitor == monitor && group_of(m.workspace) == n)
+                .map(|m| Target {
+                    workspace: m.workspace,
+                    monitor: Some(monitor.clone()),
+                })
+                .ok_or_else(|| format!("No workspace {} on {}", n, monitor))
+        }
+    }
+}
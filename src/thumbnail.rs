@@ -0,0 +1,50 @@
+use std::fs::create_dir_all;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::{get_active_workspace, paths};
+
+// Scale factor passed to grim; thumbnails only need to be recognisable
+const THUMBNAIL_SCALE: f32 = 0.2;
+
+fn thumbnail_dir() -> String {
+    format!("{}/thumbnails", paths::cache_dir())
+}
+
+// Path of the cached thumbnail for a workspace, if one has been captured
+pub fn thumbnail_path(workspace: i32) -> Option<String> {
+    let path = format!("{}/{}.png", thumbnail_dir(), workspace);
+    Path::new(&path).exists().then_some(path)
+}
+
+// Capture the active workspace in the background. socket2 only reports the
+// workspace being entered, by which point the one being left is hidden, so
+// thumbnails are taken shortly after entering and show the last visit.
+pub fn capture_active_workspace() {
+    std::thread::spawn(|| {
+        // Give Hyprland time to render the new workspace
+        std::thread::sleep(Duration::from_millis(300));
+
+        let Some(active) = get_active_workspace() else {
+            return;
+        };
+        let (id, monitor) = (active.id, active.monitor);
+        // Special workspaces have negative ids and are overlays, not worth previewing
+        if id <= 0 {
+            return;
+        }
+
+        if let Err(e) = create_dir_all(thumbnail_dir()) {
+            eprintln!("Couldn't create thumbnail directory: {}", e);
+            return;
+        }
+        let path = format!("{}/{}.png", thumbnail_dir(), id);
+        let scale = THUMBNAIL_SCALE.to_string();
+        match Command::new("grim").args(["-o", &monitor, "-s", &scale, &path]).output() {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => eprintln!("grim failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => eprintln!("Failed to run grim: {}", e),
+        }
+    });
+}
@@ -0,0 +1,61 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::get_connected_monitor_names;
+
+// Delays between monitor queries after a dock event; docks can take several
+// seconds to bring their displays up
+const BACKOFF: [u64; 6] = [250, 500, 1000, 2000, 4000, 8000];
+
+// Watch udev drm/usb events and call on_change once Hyprland reports a
+// different set of monitors. Catches docks whose displays come up after the
+// device is attached, which monitoradded alone can miss.
+pub fn watch<F: Fn()>(on_change: F) {
+    let child = Command::new("udevadm")
+        .args(["monitor", "--udev", "--subsystem-match=drm", "--subsystem-match=usb"])
+        .stdout(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Couldn't start udevadm monitor: {}", e);
+            return;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+
+    // Read udev lines on their own thread so bursts can be coalesced below
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            // e.g. "UDEV  [1234.567890] change   /devices/.../card1 (drm)"
+            if line.starts_with("UDEV") && sender.send(()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut known = get_connected_monitor_names();
+    while receiver.recv().is_ok() {
+        for delay in BACKOFF {
+            std::thread::sleep(Duration::from_millis(delay));
+            // Events from the same burst don't need another round of retries
+            while receiver.try_recv().is_ok() {}
+
+            let connected = get_connected_monitor_names();
+            if connected != known {
+                println!("Monitors changed after a udev event, reassigning workspaces...");
+                known = connected;
+                on_change();
+                break;
+            }
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
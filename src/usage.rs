@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::state;
+
+// Shades from no use to the busiest hour
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+// Seconds east of UTC, so hours are bucketed in local time
+pub fn utc_offset() -> i64 {
+    offset_from_date(&["+%z"])
+}
+
+// Seconds east of UTC at a Unix time, which differs from now's across a
+// daylight saving change
+pub fn utc_offset_at(time: u64) -> i64 {
+    offset_from_date(&["-d", &format!("@{}", time), "+%z"])
+}
+
+fn offset_from_date(args: &[&str]) -> i64 {
+    let offset = match Command::new("date").args(args).output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        Err(e) => {
+            eprintln!("Failed to run date: {}", e);
+            String::new()
+        }
+    };
+    let (sign, digits) = match offset.strip_prefix('-') {
+        Some(digits) => (-1, digits),
+        None => (1, offset.trim_start_matches('+')),
+    };
+    let hours: i64 = digits.get(..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+    let minutes: i64 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+    sign * (hours * 3600 + minutes * 60)
+}
+
+// Credit the time a workspace was focused to the hours of day it spans,
+// each in the UTC offset in effect then
+pub fn credit<F>(usage: &mut HashMap<String, Vec<u64>>, workspace: i32, from: u64, to: u64, offset: F)
+where
+    F: Fn(u64) -> i64,
+{
+    if workspace <= 0 {
+        return;
+    }
+    let hours = usage.entry(workspace.to_string()).or_insert_with(|| vec![0; 24]);
+    hours.resize(24, 0);
+
+    let mut t = from;
+    while t < to {
+        let local = t as i64 + offset(t);
+        let hour = local.div_euclid(3600).rem_euclid(24) as usize;
+        let hour_end = (t + 3600 - local.rem_euclid(3600) as u64).min(to);
+        hours[hour] += hour_end - t;
+        t = hour_end;
+    }
+}
+
+// Tracked time per workspace and hour, as a grid or JSON
+pub fn show(heatmap: bool, json: bool) {
+    let usage = state::load().usage;
+    let mut workspaces: Vec<(i32, &Vec<u64>)> = usage
+        .iter()
+        .filter_map(|(ws, hours)| Some((ws.parse().ok()?, hours)))
+        .collect();
+    workspaces.sort_by_key(|(ws, _)| *ws);
+
+    if json {
+        match serde_json::to_string(&usage) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error serializing usage: {}", e),
+        }
+        return;
+    }
+
+    if workspaces.is_empty() {
+        println!("No usage recorded yet; the daemon tracks it while running");
+        return;
+    }
+
+    if !heatmap {
+        for (ws, hours) in &workspaces {
+            let total: u64 = hours.iter().sum();
+            println!("{:>4}  {:>3}h {:02}m", ws, total / 3600, total % 3600 / 60);
+        }
+        return;
+    }
+
+    let busiest = workspaces.iter().flat_map(|(_, hours)| hours.iter()).max().copied().unwrap_or(0).max(1);
+    println!("      0     6     12    18  23");
+    for (ws, hours) in &workspaces {
+        let row: String = hours
+            .iter()
+            .map(|seconds| {
+                let level = (seconds * (SHADES.len() as u64 - 1)).div_ceil(busiest) as usize;
+                SHADES[level.min(SHADES.len() - 1)]
+            })
+            .collect();
+        println!("{:>4}  {}", ws, row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credits_each_hour_spanned() {
+        let mut usage = HashMap::new();
+        // 00:30 to 02:15 UTC
+        credit(&mut usage, 3, 1800, 8100, |_| 0);
+        assert_eq!(usage["3"][..3], [1800, 3600, 900]);
+
+        // The same span two hours east of UTC
+        let mut usage = HashMap::new();
+        credit(&mut usage, 3, 1800, 8100, |_| 7200);
+        assert_eq!(usage["3"][2..5], [1800, 3600, 900]);
+    }
+
+    #[test]
+    fn follows_offset_changes() {
+        // The clocks go back an hour at 01:00 UTC: 00:30-01:00 is at +2,
+        // 01:00-02:00 at +1 and so counts for 02:00 local again
+        let offset = |t: u64| if t < 3600 { 7200 } else { 3600 };
+        let mut usage = HashMap::new();
+        credit(&mut usage, 1, 1800, 7200, offset);
+        assert_eq!(usage["1"][2], 1800 + 3600);
+        assert_eq!(usage["1"].iter().sum::<u64>(), 5400);
+    }
+
+    #[test]
+    fn ignores_unnumbered_workspaces() {
+        let mut usage = HashMap::new();
+        credit(&mut usage, -98, 0, 3600, |_| 0);
+        assert!(usage.is_empty());
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use crate::config::{Config, WallpaperBackend};
+use crate::paths::expand_home;
+use crate::{process, role};
+
+// Show a profile's wallpapers, keyed by monitor (or role). Called once the
+// profile's monitor keywords and mapping are in place, so outputs are current.
+pub fn apply(wallpapers: &HashMap<String, String>, backend: WallpaperBackend, config: &Config) {
+    let assignments: Vec<(String, String)> = wallpapers
+        .iter()
+        .filter_map(|(monitor, path)| Some((role::resolve_or_warn(monitor, config)?, expand_home(path))))
+        .collect();
+
+    match backend {
+        WallpaperBackend::Hyprpaper => {
+            let mut preloaded: Vec<&str> = Vec::new();
+            for (_, path) in &assignments {
+                if !preloaded.contains(&path.as_str()) {
+                    process::run("hyprctl", &["hyprpaper", "preload", path]);
+                    preloaded.push(path);
+                }
+            }
+            for (monitor, path) in &assignments {
+                process::run("hyprctl", &["hyprpaper", "wallpaper", &format!("{},{}", monitor, path)]);
+            }
+            // Free the previous profile's images
+            process::run("hyprctl", &["hyprpaper", "unload", "unused"]);
+        }
+        WallpaperBackend::Swww => {
+            for (monitor, path) in &assignments {
+                process::run("swww", &["img", "--outputs", monitor, path]);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Unix time in milliseconds when the event handler took up the event it's
+/// on; 0 while it waits for the next one
+static BUSY_SINCE: AtomicU64 = AtomicU64::new(0);
+
+/// Event the handler is on, for the hang report
+static CURRENT_EVENT: Mutex<String> = Mutex::new(String::new());
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Marks the event handler busy until dropped
+pub struct Busy;
+
+pub fn busy(event: &str) -> Busy {
+    *CURRENT_EVENT.lock().unwrap_or_else(|e| e.into_inner()) = event.to_string();
+    BUSY_SINCE.store(now_ms(), Ordering::Relaxed);
+    Busy
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        BUSY_SINCE.store(0, Ordering::Relaxed);
+    }
+}
+
+// Send a state line to systemd's notification socket (sd_notify); does
+// nothing when not started by systemd
+pub fn notify(state: &str) {
+    let Ok(path) = env::var("NOTIFY_SOCKET") else {
+        return;
+    };
+    let address = match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name),
+        None => SocketAddr::from_pathname(&path),
+    };
+    let sent = UnixDatagram::unbound().and_then(|socket| socket.send_to_addr(state.as_bytes(), &address?));
+    if let Err(e) = sent {
+        eprintln!("Warning: couldn't notify systemd ({}): {}", state, e);
+    }
+}
+
+// Keepalive interval systemd asked for with WatchdogSec=, if it's for us
+fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    let usec: u64 = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+// The daemon's watchdog thread. Keepalives go to systemd at half its
+// interval while the event handler is idle or on an event for less than
+// `stall`; a handler stuck longer is reported once and the keepalives stop,
+// so systemd restarts the daemon.
+pub fn watch(stall: Duration) {
+    let interval = watchdog_interval();
+    let tick = interval.map_or(Duration::from_secs(5), |i| i / 2);
+    let mut reported: Option<u64> = None;
+
+    loop {
+        std::thread::sleep(tick);
+        let since = BUSY_SINCE.load(Ordering::Relaxed);
+        let busy_for = Duration::from_millis(if since == 0 { 0 } else { now_ms().saturating_sub(since) });
+
+        if busy_for < stall {
+            reported = None;
+            if interval.is_some() {
+                notify("WATCHDOG=1");
+            }
+            continue;
+        }
+        if reported != Some(since) {
+            reported = Some(since);
+            let event = CURRENT_EVENT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            eprintln!("Event handler stuck on '{}' for {}s", event, busy_for.as_secs());
+            if interval.is_some() {
+                notify(&format!("STATUS=Stuck on '{}'", event));
+            }
+        }
+    }
+}
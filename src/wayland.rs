@@ -0,0 +1,81 @@
+use std::io;
+use std::process::Command;
+use serde::Deserialize;
+
+use crate::Monitor;
+
+// Subset of `wlr-randr --json` output (wlr-output-management protocol)
+#[derive(Deserialize, Debug)]
+struct WlrOutput {
+    name: String,
+    #[serde(default)]
+    description: String,
+    enabled: bool,
+    #[serde(default)]
+    modes: Vec<WlrMode>,
+    #[serde(default)]
+    position: WlrPosition,
+    #[serde(default = "default_scale")]
+    scale: f32,
+    #[serde(default)]
+    transform: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct WlrPosition {
+    x: i32,
+    y: i32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+// wlr-randr transform names in wl_output order
+const TRANSFORMS: [&str; 8] = [
+    "normal", "90", "180", "270", "flipped", "flipped-90", "flipped-180", "flipped-270",
+];
+
+#[derive(Deserialize, Debug)]
+struct WlrMode {
+    width: u32,
+    height: u32,
+    refresh: f32,
+    #[serde(default)]
+    current: bool,
+}
+
+// Enumerate outputs straight from the compositor's Wayland
+// protocols, for when Hyprland's IPC isn't answering yet (early in session
+// startup). Hyprland IDs aren't available here, so IDs follow the order the
+// outputs are reported in.
+pub fn query_outputs() -> io::Result<Vec<Monitor>> {
+    let output = Command::new("wlr-randr").arg("--json").output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("wlr-randr exited with {}", output.status)));
+    }
+
+    let outputs: Vec<WlrOutput> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(outputs
+        .into_iter()
+        .enumerate()
+        .map(|(id, o)| {
+            let mode = o.modes.iter().find(|m| m.current);
+            Monitor {
+                name: o.name,
+                id: id as u32,
+                height: mode.map_or(0, |m| m.height),
+                width: mode.map_or(0, |m| m.width),
+                refresh_rate: mode.map_or(0.0, |m| m.refresh),
+                x: o.position.x,
+                y: o.position.y,
+                scale: o.scale,
+                transform: TRANSFORMS.iter().position(|t| *t == o.transform).unwrap_or(0) as u32,
+                disabled: !o.enabled,
+                description: o.description,
+            }
+        })
+        .collect())
+}
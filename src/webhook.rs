@@ -0,0 +1,221 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::config::Webhook;
+
+// Events posted by webhooks that don't list their own
+const DEFAULT_EVENTS: [&str; 5] = ["workspace", "monitoradded", "monitorremoved", "activespecial", "submap"];
+
+// Connect and read timeout for one post
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// First wait before retrying a failed post; doubled on every attempt
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+// An event with the state snapshot taken when it arrived
+#[derive(Clone)]
+pub struct Payload {
+    pub event: String,
+    pub body: serde_json::Value,
+}
+
+fn wants(webhook: &Webhook, event: &str) -> bool {
+    if webhook.events.is_empty() {
+        DEFAULT_EVENTS.contains(&event)
+    } else {
+        webhook.events.iter().any(|e| e == event)
+    }
+}
+
+// Whether any webhook posts this event, so the daemon can skip the snapshot
+pub fn wanted(webhooks: &[Webhook], event: &str) -> bool {
+    webhooks.iter().any(|w| wants(w, event))
+}
+
+// Where a webhook posts to, from its http:// URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    // Host header value; the port is only left out when it's the default
+    fn host_header(&self) -> String {
+        if self.port == 80 {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+// Split http://HOST[:PORT]/PATH; IPv6 hosts are written in brackets
+pub fn parse_url(url: &str) -> Result<Endpoint, String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("{}: only http:// URLs are supported", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rfind(':') {
+        Some(i) if !authority[i..].contains(']') => (&authority[..i], Some(&authority[i + 1..])),
+        _ => (authority, None),
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| format!("{}: invalid port", url))?,
+        None => 80,
+    };
+    if host.is_empty() || host == "[]" {
+        return Err(format!("{}: missing host", url));
+    }
+    Ok(Endpoint {
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+    })
+}
+
+fn post(webhook: &Webhook, body: &str) -> Result<(), String> {
+    let endpoint = match &webhook.endpoint {
+        Some(endpoint) => endpoint.clone(),
+        None => parse_url(&webhook.url)?,
+    };
+    let host = endpoint.host.trim_start_matches('[').trim_end_matches(']');
+    let address = (host, endpoint.port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("{} didn't resolve", host))?;
+
+    let send = || -> io::Result<String> {
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            endpoint.path,
+            endpoint.host_header(),
+            body.len()
+        );
+        for (name, value) in &webhook.headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream.write_all(request.as_bytes())?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+        Ok(status_line)
+    };
+
+    let status_line = send().map_err(|e| e.to_string())?;
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(format!("answered '{}'", status_line.trim())),
+    }
+}
+
+fn post_with_retries(webhook: &Webhook, payload: &Payload) {
+    let body = payload.body.to_string();
+    let mut delay = RETRY_DELAY;
+    for attempt in 0..=webhook.retries() {
+        match post(webhook, &body) {
+            Ok(()) => return,
+            Err(e) if attempt == webhook.retries() => {
+                eprintln!("Webhook {} failed for '{}': {}", webhook.url, payload.event, e);
+            }
+            Err(_) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+        }
+    }
+}
+
+// The daemon's webhook thread: hand each payload to the webhooks that want
+// it. Every webhook posts from its own thread, so a slow or unreachable one
+// doesn't hold up the others.
+pub fn deliver(receiver: mpsc::Receiver<Payload>, webhooks: &[Webhook]) {
+    std::thread::scope(|scope| {
+        let senders: Vec<_> = webhooks
+            .iter()
+            .map(|webhook| {
+                let (sender, receiver) = mpsc::channel();
+                scope.spawn(move || deliver_to(receiver, webhook));
+                sender
+            })
+            .collect();
+
+        for payload in receiver {
+            for (webhook, sender) in webhooks.iter().zip(&senders) {
+                if wants(webhook, &payload.event) {
+                    let _ = sender.send(payload.clone());
+                }
+            }
+        }
+    });
+}
+
+// Post payloads to one webhook no more often than its min_interval, keeping
+// only the latest payload while one waits
+fn deliver_to(receiver: mpsc::Receiver<Payload>, webhook: &Webhook) {
+    let interval = Duration::from_millis(webhook.min_interval());
+    let mut last_sent: Option<Instant> = None;
+    let mut pending: Option<Payload> = None;
+
+    loop {
+        let until_due =
+            |last_sent: Option<Instant>| last_sent.map_or(Duration::ZERO, |t| interval.saturating_sub(t.elapsed()));
+        let wait = match pending {
+            Some(_) => until_due(last_sent),
+            None => Duration::from_secs(3600),
+        };
+
+        match receiver.recv_timeout(wait) {
+            Ok(payload) => pending = Some(payload),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if !until_due(last_sent).is_zero() {
+            continue;
+        }
+        if let Some(payload) = pending.take() {
+            post_with_retries(webhook, &payload);
+            last_sent = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_urls() {
+        let endpoint = parse_url("http://ha.local:8123/api/webhook/abc").unwrap();
+        assert_eq!(
+            (endpoint.host.as_str(), endpoint.port, endpoint.path.as_str()),
+            ("ha.local", 8123, "/api/webhook/abc")
+        );
+        assert_eq!(endpoint.host_header(), "ha.local:8123");
+
+        let endpoint = parse_url("http://example.com").unwrap();
+        assert_eq!((endpoint.port, endpoint.path.as_str()), (80, "/"));
+        assert_eq!(endpoint.host_header(), "example.com");
+
+        let endpoint = parse_url("http://[::1]:8080/hook").unwrap();
+        assert_eq!((endpoint.host.as_str(), endpoint.port), ("[::1]", 8080));
+        assert_eq!(parse_url("http://[::1]/").unwrap().port, 80);
+
+        let invalid = ["https://example.com/", "example.com", "http://host:port/", "http://host:99999/", "http://:80/"];
+        for invalid in invalid {
+            assert!(parse_url(invalid).is_err(), "'{}' should be rejected", invalid);
+        }
+    }
+}